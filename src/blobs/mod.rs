@@ -22,9 +22,11 @@
 pub mod probe;
 
 use std::io::Read;
+#[cfg(feature = "mmap")]
+use std::path::Path;
 use std::{fmt, io};
 
-use bytes::{Bytes, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use prost::Message;
 use thiserror::Error;
 
@@ -32,13 +34,60 @@ use crate::proto;
 
 crate::doc_imports! {
     use self::ReadError::Decode;
+    use self::ReadError::Io;
 }
 
+/// The maximum size of a [`proto::BlobHeader`] as specified by the wiki
+pub const MAX_BLOB_HEADER_SIZE: usize = 64 * 1024;
+
+/// The maximum size of a [`proto::Blob`] (both compressed and uncompressed) as specified by the wiki
+pub const MAX_BLOB_SIZE: usize = 32 * 1024 * 1024;
+
 /// Iterate over a `.osm.pbf` file's raw chunks
 ///
 /// See the [module](self) for more information.
 pub fn iter_blobs<R: Read>(reader: R) -> BlobIter<R> {
-    BlobIter(reader)
+    BlobIter {
+        reader,
+        position: BlobPosition::default(),
+    }
+}
+
+/// Iterate over the raw chunks of an `.osm.pbf` file which is already in memory
+///
+/// Unlike [`iter_blobs`], this doesn't copy the blobs' data.
+/// Instead each [`Blob`] references a slice of `bytes`.
+pub fn iter_blobs_from_bytes(bytes: impl Into<Bytes>) -> BytesBlobIter {
+    BytesBlobIter {
+        bytes: bytes.into(),
+        position: BlobPosition::default(),
+    }
+}
+
+/// Memory-map a `.osm.pbf` file and iterate over its raw chunks
+///
+/// Like [`iter_blobs_from_bytes`], this doesn't copy the blobs' data.
+/// Instead each [`Blob`] references a slice of the map which is kept alive until the last blob is dropped.
+///
+/// ## Safety
+/// See [`map_file`].
+#[cfg(feature = "mmap")]
+pub unsafe fn iter_blobs_mmap(path: impl AsRef<Path>) -> io::Result<BytesBlobIter> {
+    Ok(iter_blobs_from_bytes(map_file(path)?))
+}
+
+/// Memory-map a file into [`Bytes`]
+///
+/// Use this to pass a memory-mapped file to functions accepting [`Bytes`] like [`crate::read_bytes`].
+///
+/// ## Safety
+/// The file must not be modified or truncated while the map (or any slice of it) is alive.
+/// Otherwise reading from the map is undefined behaviour.
+#[cfg(feature = "mmap")]
+pub unsafe fn map_file(path: impl AsRef<Path>) -> io::Result<Bytes> {
+    let file = std::fs::File::open(path)?;
+    let map = memmap2::Mmap::map(&file)?;
+    Ok(Bytes::from_owner(map))
 }
 
 /// A raw chunk of data from an `.osm.pbf` file which can be processed independently
@@ -83,40 +132,156 @@ impl fmt::Display for BlobType {
     }
 }
 
+/// A [`Blob`]'s position in its file
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlobPosition {
+    /// The number of blobs preceding this one (including the header blob)
+    pub index: usize,
+
+    /// The byte offset of the blob's length prefix from the beginning of the reader
+    pub offset: u64,
+}
+impl fmt::Display for BlobPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "blob {} at offset {}", self.index, self.offset)
+    }
+}
+
 /// Iterator produced by [`iter_blobs`]
 #[derive(Debug)]
-pub struct BlobIter<R: Read>(R);
+pub struct BlobIter<R: Read> {
+    reader: R,
+
+    /// The position of the next blob
+    position: BlobPosition,
+}
+impl<R: Read> BlobIter<R> {
+    /// The position of the blob which will be returned by the next call to [`Iterator::next`]
+    pub fn position(&self) -> BlobPosition {
+        self.position
+    }
+}
 impl<R: Read> Iterator for BlobIter<R> {
     type Item = Result<Blob, ReadError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        fn read(reader: &mut impl Read) -> Result<Option<Blob>, ReadError> {
+        fn read(reader: &mut impl Read) -> Result<Option<(Blob, u64)>, ReadError> {
             let mut buffer = [0; 4];
-            if let Err(err) = reader.read_exact(&mut buffer) {
-                return match err.kind() {
-                    io::ErrorKind::UnexpectedEof => Ok(None),
-                    _ => Err(err.into()),
-                };
+            let mut filled = 0;
+            while filled < buffer.len() {
+                match reader.read(&mut buffer[filled..]) {
+                    Ok(0) if filled == 0 => return Ok(None),
+                    Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                    Ok(written) => filled += written,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err.into()),
+                }
             }
-            let header_size = u32::from_be_bytes(buffer) as usize;
+            let header_size = check_header_size(buffer)?;
 
-            let mut buffer = vec![0; header_size as usize];
+            let mut buffer = vec![0; header_size];
             reader.read_exact(&mut buffer)?;
-            let header = proto::BlobHeader::decode(buffer.as_slice())?; // TODO: avoid String alloc
-            let body_size = header.datasize as usize;
+            let (r#type, body_size) = decode_header(&buffer)?;
 
             let mut buffer = BytesMut::zeroed(body_size);
             reader.read_exact(&mut buffer)?;
 
-            Ok(Some(Blob {
-                r#type: header.r#type.as_str().into(),
-                data: buffer.freeze(),
-            }))
+            Ok(Some((
+                Blob {
+                    r#type,
+                    data: buffer.freeze(),
+                },
+                (4 + header_size + body_size) as u64,
+            )))
         }
-        read(&mut self.0).transpose()
+        let (blob, size) = match read(&mut self.reader).transpose()? {
+            Ok(result) => result,
+            Err(error) => return Some(Err(error)),
+        };
+        self.position.index += 1;
+        self.position.offset += size;
+        Some(Ok(blob))
     }
 }
 
+/// Iterator produced by [`iter_blobs_from_bytes`]
+#[derive(Debug)]
+pub struct BytesBlobIter {
+    /// The remaining bytes starting at the next blob
+    bytes: Bytes,
+
+    /// The position of the next blob
+    position: BlobPosition,
+}
+impl BytesBlobIter {
+    /// The position of the blob which will be returned by the next call to [`Iterator::next`]
+    pub fn position(&self) -> BlobPosition {
+        self.position
+    }
+}
+impl Iterator for BytesBlobIter {
+    type Item = Result<Blob, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        fn read(bytes: &Bytes) -> Result<Option<(Blob, usize)>, ReadError> {
+            let eof = || ReadError::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+            if bytes.is_empty() {
+                return Ok(None);
+            }
+            let prefix = bytes.first_chunk().ok_or_else(eof)?;
+            let header_size = check_header_size(*prefix)?;
+
+            let header = bytes.get(4..4 + header_size).ok_or_else(eof)?;
+            let (r#type, body_size) = decode_header(header)?;
+
+            let start = 4 + header_size;
+            let end = start + body_size;
+            if end > bytes.len() {
+                return Err(eof());
+            }
+            Ok(Some((
+                Blob {
+                    r#type,
+                    data: bytes.slice(start..end),
+                },
+                end,
+            )))
+        }
+        let (blob, size) = match read(&self.bytes).transpose()? {
+            Ok(result) => result,
+            Err(error) => {
+                // The next blob can't be found after an error
+                self.bytes.clear();
+                return Some(Err(error));
+            }
+        };
+        self.bytes.advance(size);
+        self.position.index += 1;
+        self.position.offset += size as u64;
+        Some(Ok(blob))
+    }
+}
+
+/// Parse a blob's length prefix and check it against [`MAX_BLOB_HEADER_SIZE`]
+fn check_header_size(prefix: [u8; 4]) -> Result<usize, ReadError> {
+    let header_size = u32::from_be_bytes(prefix) as usize;
+    if header_size > MAX_BLOB_HEADER_SIZE {
+        return Err(ReadError::HeaderTooLarge(header_size));
+    }
+    Ok(header_size)
+}
+
+/// Decode a [`proto::BlobHeader`] and check its `datasize` against [`MAX_BLOB_SIZE`]
+fn decode_header(buffer: &[u8]) -> Result<(BlobType, usize), ReadError> {
+    let header = proto::BlobHeader::decode(buffer)?; // TODO: avoid String alloc
+    let body_size =
+        usize::try_from(header.datasize).map_err(|_| ReadError::NegativeSize(header.datasize))?;
+    if body_size > MAX_BLOB_SIZE {
+        return Err(ReadError::BlobTooLarge(body_size));
+    }
+    Ok((header.r#type.as_str().into(), body_size))
+}
+
 /// An error which occurred while reading from an `.osm.pbf` file
 ///
 /// **Note** this error is for reading not parsing.
@@ -131,13 +296,26 @@ pub enum ReadError {
     /// Failed to decode blob header
     #[error("Failed to decode blob header: {}", .0)]
     Decode(#[from] prost::DecodeError),
+
+    /// The blob header's size exceeds [`MAX_BLOB_HEADER_SIZE`]
+    #[error("Blob header is too large: {} bytes", .0)]
+    HeaderTooLarge(usize),
+
+    /// The blob's size exceeds [`MAX_BLOB_SIZE`]
+    #[error("Blob is too large: {} bytes", .0)]
+    BlobTooLarge(usize),
+
+    /// The blob header contains a negative `datasize`
+    #[error("Blob header contains a negative size: {}", .0)]
+    NegativeSize(i32),
 }
 impl From<ReadError> for io::Error {
-    /// Convert the [`Decode`] variant into an [`io::ErrorKind::InvalidData`]
+    /// Convert the non-[`Io`] variants into an [`io::ErrorKind::InvalidData`]
     fn from(value: ReadError) -> Self {
         match value {
             ReadError::Io(error) => error,
             ReadError::Decode(error) => io::Error::new(io::ErrorKind::InvalidData, error),
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
         }
     }
 }
@@ -0,0 +1,28 @@
+use crate::blocks::DataBlock;
+use crate::proto;
+
+impl DataBlock {
+    /// Iterate over the block's [`Changeset`]s
+    pub fn iter_changesets(&self) -> impl Iterator<Item = Changeset<'_>> + '_ {
+        self.block.primitivegroup.iter().flat_map(|group| {
+            group
+                .changesets
+                .iter()
+                .map(|changeset| Changeset { changeset })
+        })
+    }
+}
+
+/// An OSM changeset
+///
+/// The `.osm.pbf` format only stores a changeset's id.
+pub struct Changeset<'a> {
+    changeset: &'a proto::ChangeSet,
+}
+
+impl<'a> Changeset<'a> {
+    /// The changeset's id
+    pub fn id(&self) -> i64 {
+        self.changeset.id
+    }
+}
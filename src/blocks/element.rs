@@ -0,0 +1,201 @@
+use crate::blocks::{DataBlock, Info, Node, OwnedElement, Relation, StrIndex, Way};
+use crate::util::iter::IteratorExt;
+
+impl DataBlock {
+    /// Iterate over all of the block's [`Element`]s
+    ///
+    /// The elements are yielded in file order i.e. group by group
+    /// and within a group as nodes, ways and then relations.
+    pub fn iter_elements(&self) -> impl Iterator<Item = Element<'_>> + '_ {
+        self.block.primitivegroup.iter().flat_map(|group| {
+            self.iter_group_nodes(group)
+                .map(Element::Node)
+                .chain(self.iter_group_ways(group).map(Element::Way))
+                .chain(self.iter_group_relations(group).map(Element::Relation))
+        })
+    }
+
+    /// Drop all elements whose kind is rejected by `keep`
+    ///
+    /// Groups which don't contain any elements (or changesets) afterwards are removed.
+    pub(crate) fn retain_elements(&mut self, keep: impl Fn(ElementKind) -> bool) {
+        let nodes = keep(ElementKind::Node);
+        let ways = keep(ElementKind::Way);
+        let relations = keep(ElementKind::Relation);
+        for group in self.block.primitivegroup.iter_mut() {
+            if !nodes {
+                group.nodes.clear();
+                group.dense = None;
+            }
+            if !ways {
+                group.ways.clear();
+            }
+            if !relations {
+                group.relations.clear();
+            }
+        }
+        self.block.primitivegroup.retain(|group| {
+            !group.nodes.is_empty()
+                || group
+                    .dense
+                    .as_ref()
+                    .is_some_and(|dense| !dense.id.is_empty())
+                || !group.ways.is_empty()
+                || !group.relations.is_empty()
+                || !group.changesets.is_empty()
+        });
+    }
+
+    /// Returns true if the block doesn't contain any groups
+    pub(crate) fn is_empty(&self) -> bool {
+        self.block.primitivegroup.is_empty()
+    }
+}
+
+/// Any OSM element
+pub enum Element<'a> {
+    /// An OSM node
+    Node(Node<'a>),
+
+    /// An OSM way
+    Way(Way<'a>),
+
+    /// An OSM relation
+    Relation(Relation<'a>),
+}
+
+/// The kind of an [`Element`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    /// See [`Element::Node`]
+    Node,
+
+    /// See [`Element::Way`]
+    Way,
+
+    /// See [`Element::Relation`]
+    Relation,
+}
+
+impl<'a> Element<'a> {
+    /// The element's kind
+    pub fn kind(&self) -> ElementKind {
+        match self {
+            Element::Node(_) => ElementKind::Node,
+            Element::Way(_) => ElementKind::Way,
+            Element::Relation(_) => ElementKind::Relation,
+        }
+    }
+
+    /// The element's id
+    ///
+    /// **Note** ids are only unique among elements of the same [`ElementKind`]
+    pub fn id(&self) -> i64 {
+        match self {
+            Element::Node(node) => node.id(),
+            Element::Way(way) => way.id(),
+            Element::Relation(relation) => relation.id(),
+        }
+    }
+
+    /// Iterate over the element's tags as key-value pairs
+    pub fn tags(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        match self {
+            Element::Node(node) => node.tags().left(),
+            Element::Way(way) => way.tags().left().right(),
+            Element::Relation(relation) => relation.tags().right().right(),
+        }
+    }
+
+    /// Iterate over the element's tags' keys
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> + 'a {
+        match self {
+            Element::Node(node) => node.keys().left(),
+            Element::Way(way) => way.keys().left().right(),
+            Element::Relation(relation) => relation.keys().right().right(),
+        }
+    }
+
+    /// Iterate over the element's tags' values
+    pub fn values(&self) -> impl Iterator<Item = &'a str> + 'a {
+        match self {
+            Element::Node(node) => node.values().left(),
+            Element::Way(way) => way.values().left().right(),
+            Element::Relation(relation) => relation.values().right().right(),
+        }
+    }
+
+    /// Get the value of the element's tag with the given key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        match self {
+            Element::Node(node) => node.get(key),
+            Element::Way(way) => way.get(key),
+            Element::Relation(relation) => relation.get(key),
+        }
+    }
+
+    /// Check whether the element has a tag with the given key
+    pub fn contains_key(&self, key: &str) -> bool {
+        match self {
+            Element::Node(node) => node.contains_key(key),
+            Element::Way(way) => way.contains_key(key),
+            Element::Relation(relation) => relation.contains_key(key),
+        }
+    }
+
+    /// Check whether the element has the given tag
+    pub fn has_tag(&self, key: &str, value: &str) -> bool {
+        match self {
+            Element::Node(node) => node.has_tag(key, value),
+            Element::Way(way) => way.has_tag(key, value),
+            Element::Relation(relation) => relation.has_tag(key, value),
+        }
+    }
+
+    /// Version of [`Element::get`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn get_resolved(&self, key: StrIndex) -> Option<&'a str> {
+        match self {
+            Element::Node(node) => node.get_resolved(key),
+            Element::Way(way) => way.get_resolved(key),
+            Element::Relation(relation) => relation.get_resolved(key),
+        }
+    }
+
+    /// Version of [`Element::contains_key`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn contains_key_resolved(&self, key: StrIndex) -> bool {
+        match self {
+            Element::Node(node) => node.contains_key_resolved(key),
+            Element::Way(way) => way.contains_key_resolved(key),
+            Element::Relation(relation) => relation.contains_key_resolved(key),
+        }
+    }
+
+    /// Version of [`Element::has_tag`] taking a tag resolved using [`DataBlock::resolve`]
+    pub fn has_tag_resolved(&self, key: StrIndex, value: StrIndex) -> bool {
+        match self {
+            Element::Node(node) => node.has_tag_resolved(key, value),
+            Element::Way(way) => way.has_tag_resolved(key, value),
+            Element::Relation(relation) => relation.has_tag_resolved(key, value),
+        }
+    }
+
+    /// The element's metadata
+    ///
+    /// Returns `None` if the file omits metadata.
+    pub fn info(&self) -> Option<Info<'a>> {
+        match self {
+            Element::Node(node) => node.info(),
+            Element::Way(way) => way.info(),
+            Element::Relation(relation) => relation.info(),
+        }
+    }
+
+    /// Copy the element into an [`OwnedElement`]
+    pub fn to_owned(&self) -> OwnedElement {
+        match self {
+            Element::Node(node) => OwnedElement::Node(node.to_owned()),
+            Element::Way(way) => OwnedElement::Way(way.to_owned()),
+            Element::Relation(relation) => OwnedElement::Relation(relation.to_owned()),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::proto;
+
+crate::doc_imports! {
+    use crate::blocks::DataBlock;
+    use crate::blocks::Way;
+}
+
+/// A parsed header block storing meta-information about the file
+#[derive(Debug)]
+pub struct HeaderBlock(proto::HeaderBlock);
+impl HeaderBlock {
+    pub fn new(block: proto::HeaderBlock) -> Self {
+        Self(block)
+    }
+
+    /// Get the wrapped [`proto::HeaderBlock`]
+    pub fn as_proto(&self) -> &proto::HeaderBlock {
+        &self.0
+    }
+
+    /// Get the first required feature which is not supported by osmiumoxide
+    pub fn unknown_required_features(&self) -> Option<&str> {
+        for feature in self.0.required_features.iter() {
+            match feature.as_str() {
+                "OsmSchema-V0.6" | "DenseNodes" | "LocationsOnWays" | "HistoricalInformation" => {
+                    continue
+                }
+                _ => return Some(feature),
+            }
+        }
+        None
+    }
+
+    /// The bounding box of the file's data
+    pub fn bbox(&self) -> Option<BBox> {
+        self.0.bbox.as_ref().map(|bbox| BBox {
+            left: bbox.left,
+            right: bbox.right,
+            top: bbox.top,
+            bottom: bbox.bottom,
+        })
+    }
+
+    /// Iterate over the features a parser has to support in order to read the file
+    pub fn required_features(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.required_features.iter().map(String::as_str)
+    }
+
+    /// Iterate over the features a parser may use to read the file more efficiently
+    pub fn optional_features(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.optional_features.iter().map(String::as_str)
+    }
+
+    /// Does the file store its ways' node locations?
+    ///
+    /// If it does, [`Way::locations`] can be used to build geometries without a separate pass over the nodes.
+    pub fn has_locations_on_ways(&self) -> bool {
+        self.required_features()
+            .chain(self.optional_features())
+            .any(|feature| feature == "LocationsOnWays")
+    }
+
+    /// Does the file contain the full history i.e. multiple versions per element including deleted ones?
+    ///
+    /// See [`DataBlock::iter_node_histories`] and its siblings for how to walk an element's history.
+    pub fn has_historical_information(&self) -> bool {
+        self.required_features()
+            .any(|feature| feature == "HistoricalInformation")
+    }
+
+    /// The program which wrote the file
+    pub fn writing_program(&self) -> Option<&str> {
+        self.0.writingprogram.as_deref()
+    }
+
+    /// The file's source
+    pub fn source(&self) -> Option<&str> {
+        self.0.source.as_deref()
+    }
+
+    /// The replication timestamp in seconds since the unix epoch
+    pub fn replication_timestamp(&self) -> Option<i64> {
+        self.0.osmosis_replication_timestamp
+    }
+
+    /// The replication sequence number
+    pub fn replication_sequence_number(&self) -> Option<i64> {
+        self.0.osmosis_replication_sequence_number
+    }
+
+    /// The replication base url
+    pub fn replication_base_url(&self) -> Option<&str> {
+        self.0.osmosis_replication_base_url.as_deref()
+    }
+}
+
+/// A bounding box in nanodegrees
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BBox {
+    /// The minimum longitude
+    pub left: i64,
+
+    /// The maximum longitude
+    pub right: i64,
+
+    /// The maximum latitude
+    pub top: i64,
+
+    /// The minimum latitude
+    pub bottom: i64,
+}
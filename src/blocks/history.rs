@@ -0,0 +1,33 @@
+use crate::blocks::{DataBlock, Node, Relation, Way};
+use crate::util::iter::IteratorExt;
+
+crate::doc_imports! {
+    use crate::blocks::Info;
+}
+
+impl DataBlock {
+    /// Iterate over the block's [`Node`]s grouping all versions of one node together
+    ///
+    /// This is meant for files with historical information
+    /// in which all versions of an element are stored consecutively and ordered by their version.
+    /// Each version carries its own [`Info`] whose `visible` flag is `false` if the element was deleted in that version.
+    ///
+    /// **Note** an element's history might be split across two consecutive blocks.
+    pub fn iter_node_histories(&self) -> impl Iterator<Item = Vec<Node<'_>>> + '_ {
+        self.iter_nodes().group_by_key(Node::id)
+    }
+
+    /// Iterate over the block's [`Way`]s grouping all versions of one way together
+    ///
+    /// See [`DataBlock::iter_node_histories`] for details.
+    pub fn iter_way_histories(&self) -> impl Iterator<Item = Vec<Way<'_>>> + '_ {
+        self.iter_ways().group_by_key(Way::id)
+    }
+
+    /// Iterate over the block's [`Relation`]s grouping all versions of one relation together
+    ///
+    /// See [`DataBlock::iter_node_histories`] for details.
+    pub fn iter_relation_histories(&self) -> impl Iterator<Item = Vec<Relation<'_>>> + '_ {
+        self.iter_relations().group_by_key(Relation::id)
+    }
+}
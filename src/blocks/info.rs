@@ -0,0 +1,123 @@
+use std::iter::repeat;
+use std::num::Wrapping;
+use std::ops::Add;
+
+use crate::blocks::DataBlock;
+use crate::proto;
+use crate::util::iter::IteratorExt;
+
+/// An OSM element's metadata
+#[derive(Copy, Clone, Debug)]
+pub struct Info<'a> {
+    /// The element's version
+    ///
+    /// `-1` if the file doesn't store versions
+    pub version: i32,
+
+    /// The time of the element's last modification in milliseconds since the unix epoch
+    pub timestamp: i64,
+
+    /// The id of the changeset which last modified the element
+    pub changeset: i64,
+
+    /// The id of the user who last modified the element
+    pub uid: i32,
+
+    /// The name of the user who last modified the element
+    pub user: &'a str,
+
+    /// Is this version of the element visible or has it been deleted?
+    ///
+    /// This is only meaningful in files containing historical information and is `true` otherwise.
+    pub visible: bool,
+}
+
+/// An element's metadata as stored in a block
+///
+/// This is kept around instead of [`Info`] to delay resolving the timestamp and user name until they are requested.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct RawInfo {
+    version: i32,
+    timestamp: i64,
+    changeset: i64,
+    uid: i32,
+    user_sid: u32,
+    visible: bool,
+}
+
+impl RawInfo {
+    /// Resolve the raw timestamp and user name
+    pub(crate) fn resolve<'a>(&self, block: &'a DataBlock) -> Info<'a> {
+        Info {
+            version: self.version,
+            timestamp: block.get_time(self.timestamp),
+            changeset: self.changeset,
+            uid: self.uid,
+            user: block.get_str(self.user_sid as usize).unwrap_or(""),
+            visible: self.visible,
+        }
+    }
+}
+
+impl From<&proto::Info> for RawInfo {
+    fn from(info: &proto::Info) -> Self {
+        Self {
+            version: info.version(),
+            timestamp: info.timestamp(),
+            changeset: info.changeset(),
+            uid: info.uid(),
+            user_sid: info.user_sid(),
+            visible: info.visible.unwrap_or(true),
+        }
+    }
+}
+
+/// Decode a delta-encoded column using wrapping arithmetic
+///
+/// The metadata columns are not needed to locate elements,
+/// so hostile deltas produce garbage values instead of a panic.
+fn decode_wrapping<T>(column: &[T]) -> impl Iterator<Item = T> + '_
+where
+    T: Copy + Default,
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    column
+        .iter()
+        .map(|delta| Wrapping(*delta))
+        .decode_delta()
+        .map(|Wrapping(value)| value)
+}
+
+/// Decode a [`proto::DenseInfo`]'s columns in step with the dense nodes they belong to
+///
+/// The returned iterator is infinite and yields `None` once the columns are exhausted or if there is no `info`.
+pub(crate) fn iter_dense_info(
+    info: Option<&proto::DenseInfo>,
+) -> impl Iterator<Item = Option<RawInfo>> + '_ {
+    match info {
+        Some(info) => info
+            .version
+            .iter()
+            .copied()
+            .zip(decode_wrapping(&info.timestamp))
+            .zip(decode_wrapping(&info.changeset))
+            .zip(decode_wrapping(&info.uid))
+            .zip(decode_wrapping(&info.user_sid))
+            .zip(info.visible.iter().copied().chain(repeat(true)))
+            .map(
+                |(((((version, timestamp), changeset), uid), user_sid), visible)| {
+                    Some(RawInfo {
+                        version,
+                        timestamp,
+                        changeset,
+                        uid,
+                        user_sid: user_sid as u32,
+                        visible,
+                    })
+                },
+            )
+            .chain(repeat(None))
+            .left(),
+        None => repeat(None).right(),
+    }
+}
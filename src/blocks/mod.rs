@@ -3,21 +3,39 @@
 //! This modules parses [`Blob`]s while avoiding copying.
 //! To achieve this, some API convenience has to be sacrificed.
 
+mod changeset;
+mod element;
+mod header;
+mod history;
+mod info;
 mod node;
+mod owned;
+mod prefilter;
 mod relation;
 mod tags;
+mod validate;
 mod way;
 
 use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
 use std::str::from_utf8_unchecked;
+use std::sync::OnceLock;
 
 use bytes::Bytes;
 
+pub use self::changeset::Changeset;
+pub use self::element::{Element, ElementKind};
+pub use self::header::{BBox, HeaderBlock};
+pub use self::info::Info;
 pub use self::node::Node;
+pub use self::owned::{OwnedElement, OwnedInfo, OwnedMember, OwnedNode, OwnedRelation, OwnedWay};
 pub use self::relation::{Member, MemberType, Relation};
+pub use self::validate::{ValidationError, ValidationErrorKind};
 pub use self::way::Way;
+use crate::parse::ParseError;
 use crate::proto;
 
 crate::doc_imports! {
@@ -46,63 +64,175 @@ impl fmt::Debug for Block {
     }
 }
 
-#[derive(Debug)]
-pub struct HeaderBlock(proto::HeaderBlock);
-impl HeaderBlock {
-    pub fn new(block: proto::HeaderBlock) -> Self {
-        Self(block)
-    }
+/// How to handle strings which are not valid utf-8
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Utf8Policy {
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`
+    #[default]
+    Lossy,
 
-    pub fn unknown_required_features(&self) -> Option<&str> {
-        for feature in self.0.required_features.iter() {
-            match feature.as_str() {
-                "OsmSchema-V0.6" | "DenseNodes" => continue,
-                _ => return Some(feature),
-            }
-        }
-        None
-    }
+    /// Reject the whole block with a [`ParseError::InvalidUtf8`]
+    Strict,
+
+    /// Replace invalid sequences like [`Utf8Policy::Lossy`]
+    /// but remember the strings' indices which can be retrieved using [`DataBlock::invalid_strings`]
+    Report,
 }
 
-pub struct DataBlock(proto::PrimitiveBlock);
+pub struct DataBlock {
+    block: proto::PrimitiveBlock,
+
+    /// Lookup structure over the `stringtable` which is constructed lazily by [`DataBlock::string_index`]
+    strings: OnceLock<StringIndex>,
+
+    /// Indices of strings which were not valid utf-8 (only populated by [`Utf8Policy::Report`])
+    invalid_strings: Vec<usize>,
+}
 impl DataBlock {
     /// Wrap a [`proto::PrimitiveBlock`] to provide a sane API
     ///
     /// This performs some checks:
     /// - All strings are checked (and tweaked) to be valid utf-8
-    pub fn new(mut block: proto::PrimitiveBlock) -> Self {
-        for bytes in block.stringtable.s.iter_mut() {
-            let string = match String::from_utf8_lossy(&bytes) {
+    ///
+    /// This is the same as [`DataBlock::with_utf8_policy`] using [`Utf8Policy::Lossy`].
+    pub fn new(block: proto::PrimitiveBlock) -> Self {
+        match Self::with_utf8_policy(block, Utf8Policy::Lossy) {
+            Ok(block) => block,
+            Err(_) => unreachable!("Lossy utf-8 handling can't fail"),
+        }
+    }
+
+    /// Wrap a [`proto::PrimitiveBlock`] handling invalid utf-8 according to `policy`
+    pub fn with_utf8_policy(
+        mut block: proto::PrimitiveBlock,
+        policy: Utf8Policy,
+    ) -> Result<Self, ParseError> {
+        let mut invalid = Vec::new();
+        for (index, bytes) in block.stringtable.s.iter_mut().enumerate() {
+            let string = match String::from_utf8_lossy(bytes) {
                 Cow::Borrowed(_) => None,
                 Cow::Owned(string) => Some(string),
             };
             if let Some(string) = string {
+                match policy {
+                    Utf8Policy::Lossy => {}
+                    Utf8Policy::Strict => return Err(ParseError::InvalidUtf8(index)),
+                    Utf8Policy::Report => invalid.push(index),
+                }
                 *bytes = Bytes::from(string.into_bytes());
             }
         }
-        Self(block)
+        Ok(Self {
+            block,
+            strings: OnceLock::new(),
+            invalid_strings: invalid,
+        })
+    }
+
+    /// The indices of strings which were not valid utf-8 and have been replaced
+    ///
+    /// This is only populated if the block was constructed using [`Utf8Policy::Report`].
+    pub fn invalid_strings(&self) -> &[usize] {
+        &self.invalid_strings
+    }
+
+    /// Get the wrapped [`proto::PrimitiveBlock`]
+    pub fn as_proto(&self) -> &proto::PrimitiveBlock {
+        &self.block
     }
 
     /// Retrieve a string by its index
     fn get_str(&self, index: usize) -> Option<&str> {
-        self.0.stringtable.s.get(index).map(|bytes| unsafe {
+        self.block.stringtable.s.get(index).map(|bytes| unsafe {
             // `stringtable` is checked to be valid utf-8 in `new` and invalid utf-8 is replaced
             from_utf8_unchecked(bytes)
         })
     }
 
+    /// Look up a string in the block's string table
+    ///
+    /// Methods like [`Node::get`] perform this lookup, which hashes the string, on every call.
+    /// When querying many elements of a block for the same strings,
+    /// resolve them once and pass the result to the `..._resolved` methods (e.g. [`Node::get_resolved`]),
+    /// which only compare integers.
+    ///
+    /// Returns `None` if the string doesn't occur in the block, so none of its elements can match.
+    pub fn resolve(&self, string: &str) -> Option<StrIndex> {
+        self.string_index()
+            .first
+            .get(string.as_bytes())
+            .copied()
+            .map(StrIndex)
+    }
+
+    /// Check whether the string table's entry at `index` is the resolved `string`
+    ///
+    /// Writers are not required to deduplicate the string table,
+    /// so `index` is mapped to the first index of the same string before comparing.
+    fn matches(&self, string: StrIndex, index: u32) -> bool {
+        let canonical = &self.string_index().canonical;
+        canonical.get(index as usize).copied().unwrap_or(index) == string.0
+    }
+
+    /// Get the lookup structure over the string table, building it on first use
+    fn string_index(&self) -> &StringIndex {
+        self.strings.get_or_init(|| {
+            let strings = &self.block.stringtable.s;
+            let mut first = HashMap::with_capacity(strings.len());
+            let mut duplicates = false;
+            for (index, bytes) in strings.iter().enumerate().skip(1) {
+                match first.entry(bytes.clone()) {
+                    Entry::Occupied(_) => duplicates = true,
+                    Entry::Vacant(entry) => {
+                        entry.insert(index as u32);
+                    }
+                }
+            }
+            let canonical = if duplicates {
+                strings
+                    .iter()
+                    .enumerate()
+                    .map(|(index, bytes)| match index {
+                        0 => 0,
+                        _ => first[bytes],
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            StringIndex { first, canonical }
+        })
+    }
+
     /// Convert the raw longitude stored in a node into nanodegrees
     fn get_lon(&self, raw_lon: i64) -> i64 {
-        self.0.lon_offset.unwrap_or(0) + self.0.granularity.unwrap_or(100) as i64 * raw_lon
+        self.block.lon_offset.unwrap_or(0) + self.block.granularity.unwrap_or(100) as i64 * raw_lon
     }
 
     /// Convert the raw latitude stored in a node into nanodegrees
     fn get_lat(&self, raw_lat: i64) -> i64 {
-        self.0.lat_offset.unwrap_or(0) + self.0.granularity.unwrap_or(100) as i64 * raw_lat
+        self.block.lat_offset.unwrap_or(0) + self.block.granularity.unwrap_or(100) as i64 * raw_lat
     }
 
     /// convert the raw timestamp stored in an info object into milliseconds
     fn get_time(&self, raw_time: i64) -> i64 {
-        self.0.date_granularity.unwrap_or(1000) as i64 * raw_time
+        self.block.date_granularity.unwrap_or(1000) as i64 * raw_time
     }
 }
+
+/// A string resolved against a [`DataBlock`]'s string table using [`DataBlock::resolve`]
+///
+/// It is only meaningful for the block which resolved it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct StrIndex(u32);
+
+/// Lookup structure over a [`DataBlock`]'s string table
+struct StringIndex {
+    /// Map from strings to their first index
+    first: HashMap<Bytes, u32>,
+
+    /// Map from every index to the first index of the same string
+    ///
+    /// This is empty if the string table doesn't contain duplicates.
+    canonical: Vec<u32>,
+}
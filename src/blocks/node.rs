@@ -1,48 +1,63 @@
 use std::iter::repeat;
 
+use crate::blocks::info::{iter_dense_info, RawInfo};
 use crate::blocks::tags::Tags;
-use crate::blocks::DataBlock;
+use crate::blocks::{DataBlock, Info, StrIndex};
+use crate::coords::LatLon;
+use crate::proto;
 use crate::util::iter::IteratorExt;
 
 impl DataBlock {
     /// Iterate over the block's [`Node`]s
     pub fn iter_nodes(&self) -> impl Iterator<Item = Node<'_>> + '_ {
-        self.0.primitivegroup.iter().flat_map(|group| {
-            let nodes = group.nodes.iter().map(|node| Node {
-                block: self,
-                id: node.id,
-                lat: node.lat,
-                lon: node.lon,
-                tags: NodeTags::Normal(Tags {
-                    keys: &node.keys,
-                    vals: &node.vals,
-                }),
-            });
-            let dense_nodes = group.dense.iter().flat_map(|dense_nodes| {
-                const EMPTY_TAGS: &[i32] = &[];
-                dense_nodes
-                    .id
-                    .iter()
-                    .copied()
-                    .decode_delta()
-                    .zip(dense_nodes.lat.iter().copied().decode_delta())
-                    .zip(dense_nodes.lon.iter().copied().decode_delta())
-                    .zip(
-                        dense_nodes
-                            .keys_vals
-                            .split(|x| *x == 0)
-                            .chain(repeat(EMPTY_TAGS)),
-                    )
-                    .map(|(((id, lat), lon), keys_vals)| Node {
-                        block: self,
-                        id,
-                        lat,
-                        lon,
-                        tags: NodeTags::Dense(keys_vals),
-                    })
-            });
-            nodes.chain(dense_nodes)
-        })
+        self.block
+            .primitivegroup
+            .iter()
+            .flat_map(|group| self.iter_group_nodes(group))
+    }
+
+    /// Iterate over a single [`proto::PrimitiveGroup`]'s [`Node`]s
+    pub(crate) fn iter_group_nodes<'a>(
+        &'a self,
+        group: &'a proto::PrimitiveGroup,
+    ) -> impl Iterator<Item = Node<'a>> + 'a {
+        let nodes = group.nodes.iter().map(|node| Node {
+            block: self,
+            id: node.id,
+            lat: node.lat,
+            lon: node.lon,
+            tags: NodeTags::Normal(Tags {
+                keys: &node.keys,
+                vals: &node.vals,
+            }),
+            info: node.info.as_ref().map(RawInfo::from),
+        });
+        let dense_nodes = group.dense.iter().flat_map(|dense_nodes| {
+            const EMPTY_TAGS: &[i32] = &[];
+            dense_nodes
+                .id
+                .iter()
+                .copied()
+                .decode_delta()
+                .zip(dense_nodes.lat.iter().copied().decode_delta())
+                .zip(dense_nodes.lon.iter().copied().decode_delta())
+                .zip(
+                    dense_nodes
+                        .keys_vals
+                        .split(|x| *x == 0)
+                        .chain(repeat(EMPTY_TAGS)),
+                )
+                .zip(iter_dense_info(dense_nodes.denseinfo.as_ref()))
+                .map(|((((id, lat), lon), keys_vals), info)| Node {
+                    block: self,
+                    id,
+                    lat,
+                    lon,
+                    tags: NodeTags::Dense(keys_vals),
+                    info,
+                })
+        });
+        nodes.chain(dense_nodes)
     }
 }
 
@@ -67,6 +82,11 @@ pub struct Node<'a> {
     ///
     /// The impl is dependent on the node's origin i.e. is it stored densely or not
     tags: NodeTags<'a>,
+
+    /// The node's metadata
+    ///
+    /// The timestamp and user name are resolved lazily in [`Node::info`]
+    info: Option<RawInfo>,
 }
 
 enum NodeTags<'a> {
@@ -90,10 +110,15 @@ impl<'a> Node<'a> {
         self.block.get_lon(self.lon)
     }
 
+    /// The node's location
+    pub fn location(&self) -> LatLon {
+        LatLon::new(self.lat(), self.lon())
+    }
+
     /// Iterate over the node's tags as key-value pairs
     pub fn tags(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
         match &self.tags {
-            NodeTags::Normal(tags) => tags.iter(&self.block).left(),
+            NodeTags::Normal(tags) => tags.iter(self.block).left(),
             NodeTags::Dense(tags) => tags
                 .iter()
                 .copied()
@@ -110,7 +135,7 @@ impl<'a> Node<'a> {
     /// Iterate over the node's tags' keys
     pub fn keys(&self) -> impl Iterator<Item = &'a str> + 'a {
         match &self.tags {
-            NodeTags::Normal(tags) => tags.keys(&self.block).left(),
+            NodeTags::Normal(tags) => tags.keys(self.block).left(),
             NodeTags::Dense(tags) => tags
                 .iter()
                 .copied()
@@ -123,7 +148,7 @@ impl<'a> Node<'a> {
     /// Iterate over the node's tags' keys
     pub fn values(&self) -> impl Iterator<Item = &'a str> + 'a {
         match &self.tags {
-            NodeTags::Normal(tags) => tags.values(&self.block).left(),
+            NodeTags::Normal(tags) => tags.values(self.block).left(),
             NodeTags::Dense(tags) => tags
                 .iter()
                 .copied()
@@ -134,5 +159,64 @@ impl<'a> Node<'a> {
         }
     }
 
-    // TODO expose self.node.info
+    /// Get the value of the node's tag with the given key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.get_resolved(self.block.resolve(key)?)
+    }
+
+    /// Check whether the node has a tag with the given key
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.block
+            .resolve(key)
+            .is_some_and(|key| self.contains_key_resolved(key))
+    }
+
+    /// Check whether the node has the given tag
+    pub fn has_tag(&self, key: &str, value: &str) -> bool {
+        match (self.block.resolve(key), self.block.resolve(value)) {
+            (Some(key), Some(value)) => self.has_tag_resolved(key, value),
+            _ => false,
+        }
+    }
+
+    /// Version of [`Node::get`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn get_resolved(&self, key: StrIndex) -> Option<&'a str> {
+        match &self.tags {
+            NodeTags::Normal(tags) => tags.get(self.block, key),
+            NodeTags::Dense(tags) => tags
+                .iter()
+                .copied()
+                .chunk_pairs()
+                .find(|(k, _)| self.block.matches(key, *k as u32))
+                .and_then(|(_, value)| self.block.get_str(value as usize)),
+        }
+    }
+
+    /// Version of [`Node::contains_key`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn contains_key_resolved(&self, key: StrIndex) -> bool {
+        match &self.tags {
+            NodeTags::Normal(tags) => tags.contains_key(self.block, key),
+            NodeTags::Dense(tags) => tags
+                .iter()
+                .step_by(2)
+                .any(|k| self.block.matches(key, *k as u32)),
+        }
+    }
+
+    /// Version of [`Node::has_tag`] taking a tag resolved using [`DataBlock::resolve`]
+    pub fn has_tag_resolved(&self, key: StrIndex, value: StrIndex) -> bool {
+        match &self.tags {
+            NodeTags::Normal(tags) => tags.has_tag(self.block, key, value),
+            NodeTags::Dense(tags) => tags.iter().copied().chunk_pairs().any(|(k, v)| {
+                self.block.matches(key, k as u32) && self.block.matches(value, v as u32)
+            }),
+        }
+    }
+
+    /// The node's metadata
+    ///
+    /// Returns `None` if the file omits metadata.
+    pub fn info(&self) -> Option<Info<'a>> {
+        self.info.map(|info| info.resolve(self.block))
+    }
 }
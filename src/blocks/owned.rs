@@ -0,0 +1,186 @@
+use crate::blocks::{Info, Member, MemberType, Node, Relation, Way};
+use crate::coords::LatLon;
+
+/// An owned version of [`Node`] which doesn't borrow its [`DataBlock`](crate::blocks::DataBlock)
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedNode {
+    /// The node's id
+    pub id: i64,
+
+    /// The node's location
+    pub location: LatLon,
+
+    /// The node's tags as key-value pairs
+    pub tags: Vec<(String, String)>,
+
+    /// The node's metadata
+    pub info: Option<OwnedInfo>,
+}
+
+/// An owned version of [`Way`] which doesn't borrow its [`DataBlock`](crate::blocks::DataBlock)
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedWay {
+    /// The way's id
+    pub id: i64,
+
+    /// The way's nodes' ids
+    pub nodes: Vec<i64>,
+
+    /// The way's nodes' locations
+    ///
+    /// This is empty unless the file was written with the `LocationsOnWays` feature.
+    pub locations: Vec<LatLon>,
+
+    /// The way's tags as key-value pairs
+    pub tags: Vec<(String, String)>,
+
+    /// The way's metadata
+    pub info: Option<OwnedInfo>,
+}
+
+/// An owned version of [`Relation`] which doesn't borrow its [`DataBlock`](crate::blocks::DataBlock)
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedRelation {
+    /// The relation's id
+    pub id: i64,
+
+    /// The relation's members
+    pub members: Vec<OwnedMember>,
+
+    /// The relation's tags as key-value pairs
+    pub tags: Vec<(String, String)>,
+
+    /// The relation's metadata
+    pub info: Option<OwnedInfo>,
+}
+
+/// An owned version of [`Member`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnedMember {
+    /// The member's id
+    pub id: i64,
+
+    /// The member's type i.e node, way or relation
+    pub r#type: MemberType,
+
+    /// The member's role
+    pub role: String,
+}
+
+/// An owned version of [`Info`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnedInfo {
+    /// See [`Info::version`]
+    pub version: i32,
+
+    /// See [`Info::timestamp`]
+    pub timestamp: i64,
+
+    /// See [`Info::changeset`]
+    pub changeset: i64,
+
+    /// See [`Info::uid`]
+    pub uid: i32,
+
+    /// See [`Info::user`]
+    pub user: String,
+
+    /// See [`Info::visible`]
+    pub visible: bool,
+}
+
+/// Any owned OSM element
+#[derive(Clone, Debug, PartialEq)]
+pub enum OwnedElement {
+    /// An owned node
+    Node(OwnedNode),
+
+    /// An owned way
+    Way(OwnedWay),
+
+    /// An owned relation
+    Relation(OwnedRelation),
+}
+
+impl<'a> Node<'a> {
+    /// Copy the node into an [`OwnedNode`]
+    pub fn to_owned(&self) -> OwnedNode {
+        OwnedNode {
+            id: self.id(),
+            location: self.location(),
+            tags: owned_tags(self.tags()),
+            info: self.info().as_ref().map(Info::to_owned),
+        }
+    }
+}
+
+impl<'a> Way<'a> {
+    /// Copy the way into an [`OwnedWay`]
+    pub fn to_owned(&self) -> OwnedWay {
+        OwnedWay {
+            id: self.id(),
+            nodes: self.nodes().collect(),
+            locations: self.locations().collect(),
+            tags: owned_tags(self.tags()),
+            info: self.info().as_ref().map(Info::to_owned),
+        }
+    }
+}
+
+impl<'a> Relation<'a> {
+    /// Copy the relation into an [`OwnedRelation`]
+    pub fn to_owned(&self) -> OwnedRelation {
+        OwnedRelation {
+            id: self.id(),
+            members: self.members().map(|member| member.to_owned()).collect(),
+            tags: owned_tags(self.tags()),
+            info: self.info().as_ref().map(Info::to_owned),
+        }
+    }
+}
+
+impl<'a> Member<'a> {
+    /// Copy the member into an [`OwnedMember`]
+    pub fn to_owned(&self) -> OwnedMember {
+        OwnedMember {
+            id: self.id,
+            r#type: self.r#type,
+            role: self.role.to_string(),
+        }
+    }
+}
+
+impl<'a> Info<'a> {
+    /// Copy the metadata into an [`OwnedInfo`]
+    pub fn to_owned(&self) -> OwnedInfo {
+        OwnedInfo {
+            version: self.version,
+            timestamp: self.timestamp,
+            changeset: self.changeset,
+            uid: self.uid,
+            user: self.user.to_string(),
+            visible: self.visible,
+        }
+    }
+}
+
+impl From<OwnedNode> for OwnedElement {
+    fn from(value: OwnedNode) -> Self {
+        Self::Node(value)
+    }
+}
+impl From<OwnedWay> for OwnedElement {
+    fn from(value: OwnedWay) -> Self {
+        Self::Way(value)
+    }
+}
+impl From<OwnedRelation> for OwnedElement {
+    fn from(value: OwnedRelation) -> Self {
+        Self::Relation(value)
+    }
+}
+
+fn owned_tags<'a>(tags: impl Iterator<Item = (&'a str, &'a str)>) -> Vec<(String, String)> {
+    tags.map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
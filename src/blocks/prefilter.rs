@@ -0,0 +1,69 @@
+//! Cheap checks on a block's string table to skip irrelevant blocks before iterating their elements
+
+use crate::blocks::DataBlock;
+
+crate::doc_imports! {
+    use crate::read_par;
+}
+
+impl DataBlock {
+    /// Check whether the block's string table contains a string
+    pub fn contains_str(&self, string: &str) -> bool {
+        self.block
+            .stringtable
+            .s
+            .iter()
+            .skip(1)
+            .any(|bytes| bytes.as_ref() == string.as_bytes())
+    }
+
+    /// Check whether any of the keys might be used in the block
+    ///
+    /// Every key and value used in a block is stored in its string table.
+    /// So if a key is missing from the table, no element in the block can use it.
+    /// The opposite is not true: a key might be present but used in a different role (e.g. as value).
+    ///
+    /// For example, to only process blocks which might contain highways using [`read_par`]:
+    /// `read_par(path)?.1.filter(|block| block.may_contain_any_key(["highway"]))`
+    pub fn may_contain_any_key<'s>(&self, keys: impl IntoIterator<Item = &'s str>) -> bool {
+        let keys: Vec<&[u8]> = keys.into_iter().map(str::as_bytes).collect();
+        self.block
+            .stringtable
+            .s
+            .iter()
+            .skip(1)
+            .any(|bytes| keys.contains(&bytes.as_ref()))
+    }
+
+    /// Check whether the tag might be used in the block
+    pub fn may_contain_tag(&self, key: &str, value: &str) -> bool {
+        self.may_contain_any_tag([(key, value)])
+    }
+
+    /// Check whether any of the tags might be used in the block
+    ///
+    /// This returns `false` if a tag's key or value is missing from the block's string table.
+    /// Like [`DataBlock::may_contain_any_key`], `true` only means the block might contain one of the tags.
+    pub fn may_contain_any_tag<'s>(
+        &self,
+        tags: impl IntoIterator<Item = (&'s str, &'s str)>,
+    ) -> bool {
+        let tags: Vec<(&[u8], &[u8])> = tags
+            .into_iter()
+            .map(|(key, value)| (key.as_bytes(), value.as_bytes()))
+            .collect();
+
+        // Track which of the tags' keys and values have been found
+        let mut found = vec![(false, false); tags.len()];
+        for bytes in self.block.stringtable.s.iter().skip(1) {
+            for ((key, value), (key_found, value_found)) in tags.iter().zip(found.iter_mut()) {
+                *key_found |= bytes.as_ref() == *key;
+                *value_found |= bytes.as_ref() == *value;
+                if *key_found && *value_found {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
@@ -1,5 +1,6 @@
+use crate::blocks::info::RawInfo;
 use crate::blocks::tags::Tags;
-use crate::blocks::DataBlock;
+use crate::blocks::{DataBlock, Info, StrIndex};
 use crate::proto;
 pub use crate::proto::relation::MemberType;
 use crate::util::iter::IteratorExt;
@@ -7,15 +8,24 @@ use crate::util::iter::IteratorExt;
 impl DataBlock {
     /// Iterate over the block's [`Relation`]s
     pub fn iter_relations(&self) -> impl Iterator<Item = Relation<'_>> + '_ {
-        self.0.primitivegroup.iter().flat_map(|group| {
-            group.relations.iter().map(|relation| Relation {
-                block: self,
-                relation,
-                tags: Tags {
-                    keys: &relation.keys,
-                    vals: &relation.vals,
-                },
-            })
+        self.block
+            .primitivegroup
+            .iter()
+            .flat_map(|group| self.iter_group_relations(group))
+    }
+
+    /// Iterate over a single [`proto::PrimitiveGroup`]'s [`Relation`]s
+    pub(crate) fn iter_group_relations<'a>(
+        &'a self,
+        group: &'a proto::PrimitiveGroup,
+    ) -> impl Iterator<Item = Relation<'a>> + 'a {
+        group.relations.iter().map(|relation| Relation {
+            block: self,
+            relation,
+            tags: Tags {
+                keys: &relation.keys,
+                vals: &relation.vals,
+            },
         })
     }
 }
@@ -35,17 +45,62 @@ impl<'a> Relation<'a> {
 
     /// Iterate over the relation's tags as key-value pairs
     pub fn tags(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
-        self.tags.iter(&self.block)
+        self.tags.iter(self.block)
     }
 
     /// Iterate over the relation's tags' keys
     pub fn keys(&self) -> impl Iterator<Item = &'a str> + 'a {
-        self.tags.keys(&self.block)
+        self.tags.keys(self.block)
     }
 
     /// Iterate over the relation's tags' keys
     pub fn values(&self) -> impl Iterator<Item = &'a str> + 'a {
-        self.tags.values(&self.block)
+        self.tags.values(self.block)
+    }
+
+    /// Get the value of the relation's tag with the given key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.get_resolved(self.block.resolve(key)?)
+    }
+
+    /// Check whether the relation has a tag with the given key
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.block
+            .resolve(key)
+            .is_some_and(|key| self.contains_key_resolved(key))
+    }
+
+    /// Check whether the relation has the given tag
+    pub fn has_tag(&self, key: &str, value: &str) -> bool {
+        match (self.block.resolve(key), self.block.resolve(value)) {
+            (Some(key), Some(value)) => self.has_tag_resolved(key, value),
+            _ => false,
+        }
+    }
+
+    /// Version of [`Relation::get`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn get_resolved(&self, key: StrIndex) -> Option<&'a str> {
+        self.tags.get(self.block, key)
+    }
+
+    /// Version of [`Relation::contains_key`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn contains_key_resolved(&self, key: StrIndex) -> bool {
+        self.tags.contains_key(self.block, key)
+    }
+
+    /// Version of [`Relation::has_tag`] taking a tag resolved using [`DataBlock::resolve`]
+    pub fn has_tag_resolved(&self, key: StrIndex, value: StrIndex) -> bool {
+        self.tags.has_tag(self.block, key, value)
+    }
+
+    /// The relation's metadata
+    ///
+    /// Returns `None` if the file omits metadata.
+    pub fn info(&self) -> Option<Info<'a>> {
+        self.relation
+            .info
+            .as_ref()
+            .map(|info| RawInfo::from(info).resolve(self.block))
     }
 
     /// Iterate over the way's nodes' ids
@@ -1,4 +1,4 @@
-use crate::blocks::DataBlock;
+use crate::blocks::{DataBlock, StrIndex};
 
 pub(crate) struct Tags<'a> {
     pub(crate) keys: &'a Vec<u32>,
@@ -31,4 +31,20 @@ impl<'a> Tags<'a> {
             .iter()
             .filter_map(|value| block.get_str(*value as usize))
     }
+
+    pub(crate) fn get(&self, block: &'a DataBlock, key: StrIndex) -> Option<&'a str> {
+        let position = self.keys.iter().position(|k| block.matches(key, *k))?;
+        block.get_str(*self.vals.get(position)? as usize)
+    }
+
+    pub(crate) fn contains_key(&self, block: &'a DataBlock, key: StrIndex) -> bool {
+        self.keys.iter().any(|k| block.matches(key, *k))
+    }
+
+    pub(crate) fn has_tag(&self, block: &'a DataBlock, key: StrIndex, value: StrIndex) -> bool {
+        self.keys
+            .iter()
+            .zip(self.vals.iter())
+            .any(|(k, v)| block.matches(key, *k) && block.matches(value, *v))
+    }
 }
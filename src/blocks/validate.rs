@@ -0,0 +1,301 @@
+use std::fmt;
+
+use crate::blocks::{DataBlock, ElementKind, MemberType};
+use crate::proto;
+
+impl DataBlock {
+    /// Check the block for structural inconsistencies
+    ///
+    /// The iterators over a block's elements are lenient:
+    /// arrays of mismatching lengths are truncated to the shortest one
+    /// and members or tags referencing unknown types or strings are skipped.
+    /// This method reports every such inconsistency instead.
+    ///
+    /// Returns an empty list if the block is well-formed.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut validator = Validator {
+            strings: self.block.stringtable.s.len(),
+            errors: Vec::new(),
+        };
+        for (group_index, group) in self.block.primitivegroup.iter().enumerate() {
+            validator.group(group_index, group);
+        }
+        validator.errors
+    }
+}
+
+/// A structural inconsistency found by [`DataBlock::validate`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The index of the [`proto::PrimitiveGroup`] containing the inconsistency
+    pub group: usize,
+
+    /// The kind of element containing the inconsistency
+    pub element: ElementKind,
+
+    /// The id of the element containing the inconsistency
+    ///
+    /// This is `None` for inconsistencies in [`proto::DenseNodes`] which affect more than a single node.
+    pub id: Option<i64>,
+
+    /// The inconsistency
+    pub kind: ValidationErrorKind,
+}
+
+/// The kind of inconsistency described by a [`ValidationError`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// Two arrays which should have the same length don't
+    LengthMismatch {
+        /// The array whose length is used as reference
+        expected_field: &'static str,
+
+        /// The reference length
+        expected: usize,
+
+        /// The array whose length differs
+        field: &'static str,
+
+        /// The differing length
+        actual: usize,
+    },
+
+    /// A string index is out of the string table's range
+    InvalidStringIndex {
+        /// The array containing the index
+        field: &'static str,
+
+        /// The invalid index
+        index: i64,
+    },
+
+    /// A relation member's type is unknown
+    UnknownMemberType(i32),
+
+    /// A dense node's tags are not a list of key-value pairs
+    OddKeysVals,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.element)?;
+        if let Some(id) = self.id {
+            write!(f, " {id}")?;
+        }
+        write!(f, " in group {}: ", self.group)?;
+        match &self.kind {
+            ValidationErrorKind::LengthMismatch {
+                expected_field,
+                expected,
+                field,
+                actual,
+            } => write!(
+                f,
+                "{field} has length {actual} but {expected_field} has length {expected}"
+            ),
+            ValidationErrorKind::InvalidStringIndex { field, index } => {
+                write!(f, "{field} references unknown string {index}")
+            }
+            ValidationErrorKind::UnknownMemberType(value) => {
+                write!(f, "unknown member type {value}")
+            }
+            ValidationErrorKind::OddKeysVals => f.write_str("keys_vals contains an unpaired key"),
+        }
+    }
+}
+
+/// Helper for [`DataBlock::validate`] collecting errors
+struct Validator {
+    /// The number of strings in the block's string table
+    strings: usize,
+
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn group(&mut self, group_index: usize, group: &proto::PrimitiveGroup) {
+        let mut push = |element, id, kind| {
+            self.errors.push(ValidationError {
+                group: group_index,
+                element,
+                id,
+                kind,
+            })
+        };
+        let strings = self.strings;
+
+        for node in group.nodes.iter() {
+            let id = Some(node.id);
+            check_tags(
+                &mut push,
+                strings,
+                ElementKind::Node,
+                id,
+                &node.keys,
+                &node.vals,
+            );
+            check_info(
+                &mut push,
+                strings,
+                ElementKind::Node,
+                id,
+                node.info.as_ref(),
+            );
+        }
+
+        if let Some(dense) = group.dense.as_ref() {
+            let mut push = |kind| push(ElementKind::Node, None, kind);
+            let expected = dense.id.len();
+            check_len(&mut push, "id", expected, "lat", dense.lat.len());
+            check_len(&mut push, "id", expected, "lon", dense.lon.len());
+
+            if !dense.keys_vals.is_empty() {
+                let mut nodes = 0;
+                for keys_vals in dense.keys_vals.split(|x| *x == 0) {
+                    nodes += 1;
+                    if keys_vals.len() % 2 != 0 {
+                        push(ValidationErrorKind::OddKeysVals);
+                    }
+                    check_indices(&mut push, strings, "keys_vals", keys_vals.iter().copied());
+                }
+                // The trailing delimiter produces an additional empty slice
+                if dense.keys_vals.last() == Some(&0) {
+                    nodes -= 1;
+                }
+                check_len(&mut push, "id", expected, "keys_vals", nodes);
+            }
+
+            if let Some(info) = dense.denseinfo.as_ref() {
+                check_len(&mut push, "id", expected, "version", info.version.len());
+                check_len(&mut push, "id", expected, "timestamp", info.timestamp.len());
+                check_len(&mut push, "id", expected, "changeset", info.changeset.len());
+                check_len(&mut push, "id", expected, "uid", info.uid.len());
+                check_len(&mut push, "id", expected, "user_sid", info.user_sid.len());
+                if !info.visible.is_empty() {
+                    check_len(&mut push, "id", expected, "visible", info.visible.len());
+                }
+                // Summing in `i64` can't overflow and reports an overflowing `i32` as an invalid index
+                let mut user_sid = 0i64;
+                let user_sids = info.user_sid.iter().map(|delta| {
+                    user_sid += *delta as i64;
+                    user_sid
+                });
+                check_indices(&mut push, strings, "user_sid", user_sids);
+            }
+        }
+
+        for way in group.ways.iter() {
+            let id = Some(way.id);
+            check_tags(
+                &mut push,
+                strings,
+                ElementKind::Way,
+                id,
+                &way.keys,
+                &way.vals,
+            );
+            check_info(&mut push, strings, ElementKind::Way, id, way.info.as_ref());
+
+            let mut push = |kind| push(ElementKind::Way, id, kind);
+            if !way.lat.is_empty() || !way.lon.is_empty() {
+                check_len(&mut push, "refs", way.refs.len(), "lat", way.lat.len());
+                check_len(&mut push, "refs", way.refs.len(), "lon", way.lon.len());
+            }
+        }
+
+        for relation in group.relations.iter() {
+            let id = Some(relation.id);
+            check_tags(
+                &mut push,
+                strings,
+                ElementKind::Relation,
+                id,
+                &relation.keys,
+                &relation.vals,
+            );
+            check_info(
+                &mut push,
+                strings,
+                ElementKind::Relation,
+                id,
+                relation.info.as_ref(),
+            );
+
+            let mut push = |kind| push(ElementKind::Relation, id, kind);
+            let expected = relation.memids.len();
+            check_len(&mut push, "memids", expected, "types", relation.types.len());
+            check_len(
+                &mut push,
+                "memids",
+                expected,
+                "roles_sid",
+                relation.roles_sid.len(),
+            );
+            for r#type in relation.types.iter() {
+                if MemberType::try_from(*r#type).is_err() {
+                    push(ValidationErrorKind::UnknownMemberType(*r#type));
+                }
+            }
+            let roles = relation.roles_sid.iter().map(|index| *index as i64);
+            check_indices(&mut push, strings, "roles_sid", roles);
+        }
+    }
+}
+
+fn check_len(
+    push: &mut impl FnMut(ValidationErrorKind),
+    expected_field: &'static str,
+    expected: usize,
+    field: &'static str,
+    actual: usize,
+) {
+    if expected != actual {
+        push(ValidationErrorKind::LengthMismatch {
+            expected_field,
+            expected,
+            field,
+            actual,
+        });
+    }
+}
+
+fn check_indices(
+    push: &mut impl FnMut(ValidationErrorKind),
+    strings: usize,
+    field: &'static str,
+    indices: impl Iterator<Item = impl Into<i64>>,
+) {
+    for index in indices {
+        let index = index.into();
+        if index < 0 || index as usize >= strings {
+            push(ValidationErrorKind::InvalidStringIndex { field, index });
+        }
+    }
+}
+
+fn check_tags(
+    push: &mut impl FnMut(ElementKind, Option<i64>, ValidationErrorKind),
+    strings: usize,
+    element: ElementKind,
+    id: Option<i64>,
+    keys: &[u32],
+    vals: &[u32],
+) {
+    let mut push = |kind| push(element, id, kind);
+    check_len(&mut push, "keys", keys.len(), "vals", vals.len());
+    check_indices(&mut push, strings, "keys", keys.iter().copied());
+    check_indices(&mut push, strings, "vals", vals.iter().copied());
+}
+
+fn check_info(
+    push: &mut impl FnMut(ElementKind, Option<i64>, ValidationErrorKind),
+    strings: usize,
+    element: ElementKind,
+    id: Option<i64>,
+    info: Option<&proto::Info>,
+) {
+    if let Some(user_sid) = info.and_then(|info| info.user_sid) {
+        let mut push = |kind| push(element, id, kind);
+        check_indices(&mut push, strings, "user_sid", [user_sid].into_iter());
+    }
+}
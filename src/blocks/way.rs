@@ -1,20 +1,35 @@
+use crate::blocks::info::RawInfo;
 use crate::blocks::tags::Tags;
-use crate::blocks::DataBlock;
+use crate::blocks::{DataBlock, Info, StrIndex};
+use crate::coords::LatLon;
 use crate::proto;
 use crate::util::iter::IteratorExt;
 
+crate::doc_imports! {
+    use crate::blocks::HeaderBlock;
+}
+
 impl DataBlock {
     /// Iterate over the block's [`Way`]s
     pub fn iter_ways(&self) -> impl Iterator<Item = Way<'_>> + '_ {
-        self.0.primitivegroup.iter().flat_map(|group| {
-            group.ways.iter().map(|way| Way {
-                block: self,
-                way,
-                tags: Tags {
-                    keys: &way.keys,
-                    vals: &way.vals,
-                },
-            })
+        self.block
+            .primitivegroup
+            .iter()
+            .flat_map(|group| self.iter_group_ways(group))
+    }
+
+    /// Iterate over a single [`proto::PrimitiveGroup`]'s [`Way`]s
+    pub(crate) fn iter_group_ways<'a>(
+        &'a self,
+        group: &'a proto::PrimitiveGroup,
+    ) -> impl Iterator<Item = Way<'a>> + 'a {
+        group.ways.iter().map(|way| Way {
+            block: self,
+            way,
+            tags: Tags {
+                keys: &way.keys,
+                vals: &way.vals,
+            },
         })
     }
 }
@@ -34,21 +49,82 @@ impl<'a> Way<'a> {
 
     /// Iterate over the way's tags as key-value pairs
     pub fn tags(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
-        self.tags.iter(&self.block)
+        self.tags.iter(self.block)
     }
 
     /// Iterate over the way's tags' keys
     pub fn keys(&self) -> impl Iterator<Item = &'a str> + 'a {
-        self.tags.keys(&self.block)
+        self.tags.keys(self.block)
     }
 
     /// Iterate over the way's tags' keys
     pub fn values(&self) -> impl Iterator<Item = &'a str> + 'a {
-        self.tags.values(&self.block)
+        self.tags.values(self.block)
+    }
+
+    /// Get the value of the way's tag with the given key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.get_resolved(self.block.resolve(key)?)
+    }
+
+    /// Check whether the way has a tag with the given key
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.block
+            .resolve(key)
+            .is_some_and(|key| self.contains_key_resolved(key))
+    }
+
+    /// Check whether the way has the given tag
+    pub fn has_tag(&self, key: &str, value: &str) -> bool {
+        match (self.block.resolve(key), self.block.resolve(value)) {
+            (Some(key), Some(value)) => self.has_tag_resolved(key, value),
+            _ => false,
+        }
+    }
+
+    /// Version of [`Way::get`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn get_resolved(&self, key: StrIndex) -> Option<&'a str> {
+        self.tags.get(self.block, key)
+    }
+
+    /// Version of [`Way::contains_key`] taking a key resolved using [`DataBlock::resolve`]
+    pub fn contains_key_resolved(&self, key: StrIndex) -> bool {
+        self.tags.contains_key(self.block, key)
+    }
+
+    /// Version of [`Way::has_tag`] taking a tag resolved using [`DataBlock::resolve`]
+    pub fn has_tag_resolved(&self, key: StrIndex, value: StrIndex) -> bool {
+        self.tags.has_tag(self.block, key, value)
+    }
+
+    /// The way's metadata
+    ///
+    /// Returns `None` if the file omits metadata.
+    pub fn info(&self) -> Option<Info<'a>> {
+        self.way
+            .info
+            .as_ref()
+            .map(|info| RawInfo::from(info).resolve(self.block))
     }
 
     /// Iterate over the way's nodes' ids
     pub fn nodes(&self) -> impl Iterator<Item = i64> + 'a {
         self.way.refs.iter().copied().decode_delta()
     }
+
+    /// Iterate over the way's nodes' locations
+    ///
+    /// Locations are only stored in files which were written with the `LocationsOnWays` feature
+    /// (see [`HeaderBlock::has_locations_on_ways`]).
+    /// For all other files this iterator is empty.
+    pub fn locations(&self) -> impl Iterator<Item = LatLon> + 'a {
+        let block = self.block;
+        self.way
+            .lat
+            .iter()
+            .copied()
+            .decode_delta()
+            .zip(self.way.lon.iter().copied().decode_delta())
+            .map(move |(lat, lon)| LatLon::new(block.get_lat(lat), block.get_lon(lon)))
+    }
 }
@@ -2,14 +2,9 @@ use std::collections::BTreeSet;
 use std::ops::Range;
 
 use crate::blocks::{DataBlock, MemberType};
+pub use crate::coords::LatLon;
 use crate::util::BSMap;
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct LatLon {
-    pub lat: i64,
-    pub lon: i64,
-}
-
 #[derive(Debug, Default)]
 pub struct PreCollector {
     /// Set of all nodes referenced by ways
@@ -29,8 +24,7 @@ impl PreCollector {
             self.nodes.extend(way.nodes());
         }
         for relation in block.iter_relations() {
-            if matches!(relation.tags().find(|(key, _)| *key == "type"), Some((_, value)) if value == "multipolygon")
-            {
+            if relation.has_tag("type", "multipolygon") {
                 self.ways.extend(
                     relation.members().filter_map(|member| {
                         (member.r#type == MemberType::Way).then_some(member.id)
@@ -76,8 +70,7 @@ impl Collector {
     pub fn collect_block(&mut self, block: DataBlock) {
         for node in block.iter_nodes() {
             if let Some(slot) = self.nodes.get_mut(&node.id()) {
-                slot.lat = node.lat();
-                slot.lon = node.lon();
+                *slot = node.location();
             }
         }
         for way in block.iter_ways() {
@@ -0,0 +1,108 @@
+//! Geographic coordinates
+
+use std::fmt;
+
+/// The mean earth radius in meters used by [`LatLon::haversine_distance`]
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// A location on earth stored as latitude and longitude in nanodegrees
+///
+/// It is displayed as `lat,lon` in degrees with 7 decimal places like the OSM API does.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LatLon {
+    /// The latitude in nanodegrees
+    pub lat: i64,
+
+    /// The longitude in nanodegrees
+    pub lon: i64,
+}
+
+impl LatLon {
+    /// Construct a location from latitude and longitude in nanodegrees
+    pub fn new(lat: i64, lon: i64) -> Self {
+        Self { lat, lon }
+    }
+
+    /// Construct a location from latitude and longitude in degrees
+    pub fn from_degrees(lat: f64, lon: f64) -> Self {
+        Self {
+            lat: (lat * 1e9).round() as i64,
+            lon: (lon * 1e9).round() as i64,
+        }
+    }
+
+    /// Construct a location from latitude and longitude in 1e-7 degrees
+    ///
+    /// This is the fixed-point representation used by OSM's database and libosmium.
+    pub fn from_fixed(lat: i32, lon: i32) -> Self {
+        Self {
+            lat: lat as i64 * 100,
+            lon: lon as i64 * 100,
+        }
+    }
+
+    /// The latitude in degrees
+    pub fn lat_degrees(&self) -> f64 {
+        self.lat as f64 * 1e-9
+    }
+
+    /// The longitude in degrees
+    pub fn lon_degrees(&self) -> f64 {
+        self.lon as f64 * 1e-9
+    }
+
+    /// The latitude in 1e-7 degrees rounded to the nearest value
+    pub fn lat_fixed(&self) -> i32 {
+        nano_to_fixed(self.lat)
+    }
+
+    /// The longitude in 1e-7 degrees rounded to the nearest value
+    pub fn lon_fixed(&self) -> i32 {
+        nano_to_fixed(self.lon)
+    }
+
+    /// Check whether the latitude is within ±90° and the longitude within ±180°
+    pub fn is_valid(&self) -> bool {
+        const MAX_LAT: i64 = 90_000_000_000;
+        const MAX_LON: i64 = 180_000_000_000;
+        (-MAX_LAT..=MAX_LAT).contains(&self.lat) && (-MAX_LON..=MAX_LON).contains(&self.lon)
+    }
+
+    /// The great-circle distance to another location in meters
+    ///
+    /// This uses the haversine formula which assumes a spherical earth with radius [`EARTH_RADIUS`].
+    pub fn haversine_distance(&self, other: &Self) -> f64 {
+        let lat1 = self.lat_degrees().to_radians();
+        let lat2 = other.lat_degrees().to_radians();
+        let delta_lat = lat2 - lat1;
+        let delta_lon = (other.lon_degrees() - self.lon_degrees()).to_radians();
+
+        let a = (delta_lat / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+}
+
+impl fmt::Display for LatLon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_fixed(f: &mut fmt::Formatter<'_>, fixed: i32) -> fmt::Result {
+            let sign = if fixed < 0 { "-" } else { "" };
+            let fixed = fixed.unsigned_abs();
+            write!(f, "{sign}{}.{:07}", fixed / 10_000_000, fixed % 10_000_000)
+        }
+        write_fixed(f, self.lat_fixed())?;
+        f.write_str(",")?;
+        write_fixed(f, self.lon_fixed())
+    }
+}
+
+/// Convert nanodegrees into 1e-7 degrees rounding half away from zero
+fn nano_to_fixed(nano: i64) -> i32 {
+    let rounded = if nano < 0 {
+        nano.saturating_sub(50)
+    } else {
+        nano.saturating_add(50)
+    };
+    let rounded = rounded / 100;
+    rounded.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
@@ -0,0 +1,189 @@
+//! libosmium-style callbacks
+//!
+//! Implement [`Handler`] for your processing logic and drive it using [`apply`] or [`apply_par`]
+//! with the blocks returned by [`read`] or [`read_par`].
+//!
+//! Several handlers can be run in a single pass by combining them in a tuple or [`Vec`].
+
+use rayon::prelude::*;
+
+use crate::blocks::{Changeset, DataBlock, Element, Node, Relation, Way};
+
+crate::doc_imports! {
+    use crate::read;
+    use crate::read_par;
+}
+
+/// Callbacks invoked for every element in a file
+///
+/// All methods default to doing nothing, so only implement the ones you need.
+///
+/// Several handlers can be chained by putting them in a `Vec` or a tuple which calls them in order.
+pub trait Handler {
+    /// Called for every node
+    fn node(&mut self, _node: &Node<'_>) {}
+
+    /// Called for every way
+    fn way(&mut self, _way: &Way<'_>) {}
+
+    /// Called for every relation
+    fn relation(&mut self, _relation: &Relation<'_>) {}
+
+    /// Called for every changeset
+    fn changeset(&mut self, _changeset: &Changeset<'_>) {}
+
+    /// Called after all elements of a block have been processed
+    fn flush(&mut self) {}
+}
+
+/// Feed every element of every block into a [`Handler`]
+///
+/// The elements are passed in file order followed by [`Handler::flush`] at the end of each block.
+pub fn apply(blocks: impl IntoIterator<Item = DataBlock>, handler: &mut impl Handler) {
+    for block in blocks {
+        apply_block(&block, handler);
+    }
+}
+
+/// Feed every element of every block into a [`Handler`]
+///
+/// [`rayon`] version of [`apply`]
+///
+/// Since blocks are processed in parallel, each thread gets its own handler constructed by `init`.
+/// All handlers are returned so their results can be merged afterwards.
+pub fn apply_par<H: Handler + Send>(
+    blocks: impl ParallelIterator<Item = DataBlock>,
+    init: impl Fn() -> H + Sync + Send,
+) -> Vec<H> {
+    blocks
+        .fold(&init, |mut handler, block| {
+            apply_block(&block, &mut handler);
+            handler
+        })
+        .collect()
+}
+
+/// Feed every element of a single block into a [`Handler`]
+pub fn apply_block(block: &DataBlock, handler: &mut impl Handler) {
+    for element in block.iter_elements() {
+        match element {
+            Element::Node(node) => handler.node(&node),
+            Element::Way(way) => handler.way(&way),
+            Element::Relation(relation) => handler.relation(&relation),
+        }
+    }
+    for changeset in block.iter_changesets() {
+        handler.changeset(&changeset);
+    }
+    handler.flush();
+}
+
+impl<H: Handler + ?Sized> Handler for &mut H {
+    fn node(&mut self, node: &Node<'_>) {
+        (**self).node(node)
+    }
+
+    fn way(&mut self, way: &Way<'_>) {
+        (**self).way(way)
+    }
+
+    fn relation(&mut self, relation: &Relation<'_>) {
+        (**self).relation(relation)
+    }
+
+    fn changeset(&mut self, changeset: &Changeset<'_>) {
+        (**self).changeset(changeset)
+    }
+
+    fn flush(&mut self) {
+        (**self).flush()
+    }
+}
+
+impl<H: Handler + ?Sized> Handler for Box<H> {
+    fn node(&mut self, node: &Node<'_>) {
+        (**self).node(node)
+    }
+
+    fn way(&mut self, way: &Way<'_>) {
+        (**self).way(way)
+    }
+
+    fn relation(&mut self, relation: &Relation<'_>) {
+        (**self).relation(relation)
+    }
+
+    fn changeset(&mut self, changeset: &Changeset<'_>) {
+        (**self).changeset(changeset)
+    }
+
+    fn flush(&mut self) {
+        (**self).flush()
+    }
+}
+
+impl<H: Handler> Handler for Vec<H> {
+    fn node(&mut self, node: &Node<'_>) {
+        self.iter_mut().for_each(|handler| handler.node(node))
+    }
+
+    fn way(&mut self, way: &Way<'_>) {
+        self.iter_mut().for_each(|handler| handler.way(way))
+    }
+
+    fn relation(&mut self, relation: &Relation<'_>) {
+        self.iter_mut()
+            .for_each(|handler| handler.relation(relation))
+    }
+
+    fn changeset(&mut self, changeset: &Changeset<'_>) {
+        self.iter_mut()
+            .for_each(|handler| handler.changeset(changeset))
+    }
+
+    fn flush(&mut self) {
+        self.iter_mut().for_each(|handler| handler.flush())
+    }
+}
+
+/// Implement [`Handler`] for tuples of handlers which chains them by calling them in order
+macro_rules! impl_handler_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Handler),+> Handler for ($($name,)+) {
+            fn node(&mut self, node: &Node<'_>) {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $($name.node(node);)+
+            }
+
+            fn way(&mut self, way: &Way<'_>) {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $($name.way(way);)+
+            }
+
+            fn relation(&mut self, relation: &Relation<'_>) {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $($name.relation(relation);)+
+            }
+
+            fn changeset(&mut self, changeset: &Changeset<'_>) {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $($name.changeset(changeset);)+
+            }
+
+            fn flush(&mut self) {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $($name.flush();)+
+            }
+        }
+    };
+}
+impl_handler_tuple!(A, B);
+impl_handler_tuple!(A, B, C);
+impl_handler_tuple!(A, B, C, D);
+impl_handler_tuple!(A, B, C, D, E);
+impl_handler_tuple!(A, B, C, D, E, F);
@@ -1,100 +1,131 @@
-use std::fs::File;
 use std::io;
+use std::io::Read;
 use std::path::Path;
 
-use log::{debug, error, trace, warn};
+use bytes::Bytes;
 use rayon::prelude::*;
 use thiserror::Error;
 
-use crate::blobs::{iter_blobs, Blob, ReadError};
-use crate::blocks::{Block, DataBlock};
-use crate::parse::{parse_blob, ParseError};
+use crate::blobs::{BlobPosition, ReadError};
+use crate::blocks::{DataBlock, HeaderBlock, ValidationError};
+use crate::parse::ParseError;
+use crate::reader::{ErrorPolicy, ReaderOptions};
 
 pub mod blobs;
 pub mod blocks;
 pub mod collector;
+pub mod coords;
+pub mod handler;
 pub mod parse;
+mod pipeline;
+pub mod reader;
 pub mod util;
+pub mod write;
 
 /// Auto-generated protobuf messages
 pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/osmpbf.rs"));
 }
 
-/// Read a `.osm.pbf` file and return an iterator over its blocks
+crate::doc_imports! {
+    use crate::blobs::Blob;
+}
+
+/// Read a `.osm.pbf` file and return its header and an iterator over its blocks
 ///
 /// This function is the simplest way to read a file but it is also offers the least amount of control:
 /// - All errors during iteration are logged.
 /// - File errors terminate the iterator.
 /// - All other errors just skip their block.
 ///
-/// When this function doesn't suffice (you need more error handling or control over speed),
-/// use [`blobs::iter_blobs`] to iterate over the file's [`Blob`]s
+/// When this function doesn't suffice, use [`ReaderOptions`] to configure the reading
+/// or [`blobs::iter_blobs`] to iterate over the file's [`Blob`]s
 /// and [`parse::parse_blob`] to decompress and decode them.
-pub fn read(path: impl AsRef<Path>) -> Result<impl Iterator<Item = DataBlock>, Error> {
-    Ok(read_process_header(path.as_ref())?
-        .take_while(Result::is_ok)
-        .filter_map(read_process_block))
+pub fn read(
+    path: impl AsRef<Path>,
+) -> Result<(HeaderBlock, impl Iterator<Item = DataBlock>), Error> {
+    let (header, blocks) = ReaderOptions::new().read(path)?;
+    Ok((header, blocks.filter_map(Result::ok)))
 }
 
-/// Read a `.osm.pbf` file and return an iterator over its blocks
+/// Read a `.osm.pbf` file from a reader and return its header and an iterator over its blocks
 ///
-/// [`rayon`] version of [`read`]
-pub fn read_par(path: impl AsRef<Path>) -> Result<impl ParallelIterator<Item = DataBlock>, Error> {
-    Ok(read_process_header(path.as_ref())?
-        .par_bridge()
-        .take_any_while(Result::is_ok)
-        .filter_map(read_process_block))
+/// Version of [`read`] for streams like stdin or a network connection
+pub fn read_from<R: Read>(
+    reader: R,
+) -> Result<(HeaderBlock, impl Iterator<Item = DataBlock>), Error> {
+    let (header, blocks) = ReaderOptions::new().read_from(reader)?;
+    Ok((header, blocks.filter_map(Result::ok)))
 }
 
-/// Helper function used in `read...` to open the file and process its header
-fn read_process_header(
-    path: &Path,
-) -> Result<impl Iterator<Item = Result<Blob, ReadError>>, Error> {
-    let mut blobs = iter_blobs(File::open(path).map_err(Error::FileError)?);
+/// Read a `.osm.pbf` file from memory and return its header and an iterator over its blocks
+///
+/// Version of [`read`] which doesn't copy the blobs (see [`blobs::iter_blobs_from_bytes`])
+pub fn read_bytes(
+    bytes: impl Into<Bytes>,
+) -> Result<(HeaderBlock, impl Iterator<Item = DataBlock>), Error> {
+    let (header, blocks) = ReaderOptions::new().read_bytes(bytes)?;
+    Ok((header, blocks.filter_map(Result::ok)))
+}
 
-    let blob = blobs.next().ok_or(Error::MissingHeader)??;
-    let Block::Header(header) = parse_blob(blob)? else {
-        return Err(Error::MissingHeader);
-    };
+/// Read a `.osm.pbf` file and return its header and an iterator over its blocks
+///
+/// [`rayon`] version of [`read`]
+///
+/// See [`ReaderOptions`] for the thread count and memory usage.
+pub fn read_par(
+    path: impl AsRef<Path>,
+) -> Result<(HeaderBlock, impl ParallelIterator<Item = DataBlock>), Error> {
+    let (header, blocks) = ReaderOptions::new().read_par(path)?;
+    Ok((header, blocks.filter_map(Result::ok)))
+}
 
-    trace!("File header: {header:#?}");
-    if let Some(feature) = header.unknown_required_features() {
-        return Err(Error::UnknownFeature(feature.to_string()));
-    }
+/// Read a `.osm.pbf` file and return its header and an iterator over its blocks
+///
+/// Parallel version of [`read`] which preserves the blocks' order:
+/// blobs are decompressed and parsed on a dedicated thread pool while the iterator yields them in file order.
+///
+/// Use this instead of [`read_par`] if your processing relies on the file's order (e.g. nodes before ways).
+pub fn read_par_ordered(
+    path: impl AsRef<Path>,
+) -> Result<(HeaderBlock, impl Iterator<Item = DataBlock>), Error> {
+    let (header, blocks) = ReaderOptions::new().read_par_ordered(path)?;
+    Ok((header, blocks.filter_map(Result::ok)))
+}
 
-    return Ok(blobs);
+/// Read a `.osm.pbf` file and return its header and an iterator over its blocks or errors
+///
+/// Unlike [`read`], this function reports errors instead of logging them:
+/// - Errors while reading the file are yielded and terminate the iterator.
+/// - All other errors are yielded and the iterator continues with the next block.
+///
+/// Each error is wrapped in an [`Error::Blob`] which states the position of the blob which caused it.
+/// So a truncated file can be distinguished from one which was read completely.
+///
+/// This is the same as [`ReaderOptions::read`] using [`ErrorPolicy::Return`].
+pub fn try_read(
+    path: impl AsRef<Path>,
+) -> Result<(HeaderBlock, impl Iterator<Item = Result<DataBlock, Error>>), Error> {
+    ReaderOptions::new()
+        .with_error_policy(ErrorPolicy::Return)
+        .read(path)
 }
 
-/// Helper function used in `read...` to process the stream of blocks
-fn read_process_block(result: Result<Blob, ReadError>) -> Option<DataBlock> {
-    let blob = match result {
-        Ok(raw) => raw,
-        Err(err) => {
-            error!("Failed to read file");
-            debug!("Failed to read file: {err}");
-            return None;
-        }
-    };
-    let block = match parse_blob(blob) {
-        Ok(block) => block,
-        Err(err) => {
-            error!("Failed to parse block");
-            debug!("Failed to parse block: {err}");
-            return None;
-        }
-    };
-    match block {
-        Block::Header(_) => {
-            warn!("Skipping header block");
-            None
-        }
-        Block::Data(block) => Some(block),
-        Block::Unknown(string, _) => {
-            warn!("Skipping unknown block of type \"{string}\"");
-            None
-        }
-    }
+/// Read a `.osm.pbf` file and return its header and an iterator over its blocks or errors
+///
+/// [`rayon`] version of [`try_read`]
+pub fn try_read_par(
+    path: impl AsRef<Path>,
+) -> Result<
+    (
+        HeaderBlock,
+        impl ParallelIterator<Item = Result<DataBlock, Error>>,
+    ),
+    Error,
+> {
+    ReaderOptions::new()
+        .with_error_policy(ErrorPolicy::Return)
+        .read_par(path)
 }
 
 #[derive(Error, Debug)]
@@ -111,6 +142,28 @@ pub enum Error {
     #[error("Failed to decode compression: {}", .0)]
     ComprError(io::Error),
 
+    /// A blob uses a compression not supported by osmiumoxide
+    #[error("Unsupported compression: {}", .0)]
+    UnsupportedCompression(&'static str),
+
+    /// A block's string table contains invalid utf-8 at the given index
+    #[error("Invalid utf-8 in string table at index {}", .0)]
+    InvalidUtf8(usize),
+
+    /// A block is structurally inconsistent
+    #[error("Malformed block: {} inconsistencies", .0.len())]
+    Malformed(Vec<ValidationError>),
+
+    /// Wraps another error with the position of the blob which caused it
+    #[error("Error in {position}: {error}")]
+    Blob {
+        /// The position of the blob which caused the error
+        position: BlobPosition,
+
+        /// The actual error
+        error: Box<Error>,
+    },
+
     /// The `.osm.pbf` file is missing its header block
     #[error("Missing header block")]
     MissingHeader,
@@ -118,12 +171,17 @@ pub enum Error {
     /// The `.osm.pbf` requires a feature not supported by osmiumoxide
     #[error("Unsupported feature: {}", .0)]
     UnknownFeature(String),
+
+    /// Failed to build the thread pool for parallel reading
+    #[error("Failed to build thread pool: {}", .0)]
+    ThreadPool(rayon::ThreadPoolBuildError),
 }
 impl From<ReadError> for Error {
     fn from(value: ReadError) -> Self {
         match value {
             ReadError::Io(error) => Self::FileError(error),
             ReadError::Decode(error) => Self::ProstError(error),
+            error => Self::FileError(error.into()),
         }
     }
 }
@@ -132,6 +190,10 @@ impl From<ParseError> for Error {
         match value {
             ParseError::Io(error) => Self::ComprError(error),
             ParseError::Decode(error) => Self::ProstError(error),
+            ParseError::UnsupportedCompression(name) => Self::UnsupportedCompression(name),
+            ParseError::InvalidUtf8(index) => Self::InvalidUtf8(index),
+            ParseError::Malformed(errors) => Self::Malformed(errors),
+            error => Self::ComprError(io::Error::new(io::ErrorKind::InvalidData, error)),
         }
     }
 }
@@ -6,42 +6,162 @@ use flate2::bufread;
 use prost::Message;
 use thiserror::Error;
 
-use crate::blobs::{Blob, BlobType};
-use crate::blocks::{Block, DataBlock, HeaderBlock};
+use crate::blobs::{Blob, BlobType, MAX_BLOB_SIZE};
+use crate::blocks::{Block, DataBlock, HeaderBlock, Utf8Policy, ValidationError};
 use crate::proto;
 pub use crate::proto::blob::Data as BlockCompression;
 
 pub fn parse_blob(blob: Blob) -> Result<Block, ParseError> {
+    parse_blob_with_options(blob, ParseOptions::default())
+}
+
+/// Options controlling how strict [`parse_blob_with_options`] is
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// How to handle strings which are not valid utf-8
+    pub utf8: Utf8Policy,
+
+    /// Reject blocks which fail [`DataBlock::validate`] with a [`ParseError::Malformed`]
+    pub strict: bool,
+}
+
+/// Same as [`parse_blob`] but allows to configure how strict the parsing is
+pub fn parse_blob_with_options(blob: Blob, options: ParseOptions) -> Result<Block, ParseError> {
     let Blob { r#type, data } = blob;
 
     // Decode outer proto
     let proto::Blob { raw_size, data } = proto::Blob::decode(data)?;
 
+    // Validate the decompressed size before it is used to allocate buffers
+    let raw_size = raw_size
+        .map(|size| {
+            usize::try_from(size)
+                .ok()
+                .filter(|size| *size <= MAX_BLOB_SIZE)
+                .ok_or(ParseError::InvalidRawSize(size))
+        })
+        .transpose()?;
+
     // Decompress
     let raw = match data.unwrap_or(BlockCompression::Raw(Bytes::new())) {
         BlockCompression::Raw(raw) => raw,
         BlockCompression::ZlibData(encoded) => {
-            let size_hint = raw_size
-                .and_then(|x| usize::try_from(x).ok())
-                .unwrap_or(encoded.len());
-            let mut decoder = bufread::ZlibDecoder::new(encoded.reader());
-            let mut decoded = Vec::with_capacity(size_hint);
-            decoder.read_to_end(&mut decoded)?;
-            decoded.into()
+            let size_hint = raw_size.unwrap_or(encoded.len());
+            let decoder = bufread::ZlibDecoder::new(encoded.reader());
+            read_limited(decoder, size_hint, raw_size.unwrap_or(MAX_BLOB_SIZE))?
+        }
+        BlockCompression::ZstdData(encoded) => {
+            let size_hint = raw_size.unwrap_or(encoded.len());
+            let decoder = zstd::Decoder::with_buffer(encoded.reader())?;
+            read_limited(decoder, size_hint, raw_size.unwrap_or(MAX_BLOB_SIZE))?
+        }
+        #[cfg(feature = "lz4")]
+        BlockCompression::Lz4Data(encoded) => {
+            // The lz4 block format doesn't store its decompressed size
+            let size = raw_size.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "lz4 blob is missing raw_size")
+            })?;
+            lz4_flex::block::decompress(&encoded, size)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+                .into()
+        }
+        #[cfg(feature = "lzma")]
+        BlockCompression::LzmaData(encoded) => {
+            let limit = raw_size.unwrap_or(MAX_BLOB_SIZE);
+            let mut decoded = LimitedWriter {
+                buffer: Vec::with_capacity(raw_size.unwrap_or(encoded.len()).min(limit)),
+                limit,
+                exceeded: false,
+            };
+            let result = lzma_rs::lzma_decompress(&mut encoded.reader(), &mut decoded);
+            if decoded.exceeded {
+                return Err(ParseError::TooLarge(limit));
+            }
+            result.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            decoded.buffer.into()
+        }
+        unsupported => {
+            return Err(ParseError::UnsupportedCompression(compression_name(
+                &unsupported,
+            )))
         }
-        _ => unimplemented!("Unsupported format"),
     };
 
     // Decode inner proto
     let block = match r#type {
         BlobType::OSMHeader => Block::Header(HeaderBlock::new(proto::HeaderBlock::decode(raw)?)),
-        BlobType::OSMData => Block::Data(DataBlock::new(proto::PrimitiveBlock::decode(raw)?)),
+        BlobType::OSMData => {
+            let block =
+                DataBlock::with_utf8_policy(proto::PrimitiveBlock::decode(raw)?, options.utf8)?;
+            if options.strict {
+                let errors = block.validate();
+                if !errors.is_empty() {
+                    return Err(ParseError::Malformed(errors));
+                }
+            }
+            Block::Data(block)
+        }
         BlobType::Unknown(string) => Block::Unknown(string, raw),
     };
 
     Ok(block)
 }
 
+/// Read a decoder to its end while refusing to produce more than `limit` bytes
+///
+/// This protects against decompression bombs i.e. small blobs which decompress to huge amounts of data.
+fn read_limited(decoder: impl Read, size_hint: usize, limit: usize) -> Result<Bytes, ParseError> {
+    let mut decoded = Vec::with_capacity(size_hint.min(limit));
+    decoder.take(limit as u64 + 1).read_to_end(&mut decoded)?;
+    if decoded.len() > limit {
+        return Err(ParseError::TooLarge(limit));
+    }
+    Ok(decoded.into())
+}
+
+/// Writer collecting into a buffer while refusing to produce more than `limit` bytes
+///
+/// This is [`read_limited`]'s counterpart for decoders which write their output.
+#[cfg(feature = "lzma")]
+struct LimitedWriter {
+    buffer: Vec<u8>,
+    limit: usize,
+
+    /// Set once a write would have exceeded the limit
+    exceeded: bool,
+}
+
+#[cfg(feature = "lzma")]
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buffer.len() + buf.len() > self.limit {
+            self.exceeded = true;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "decompressed data exceeds limit",
+            ));
+        }
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Get the name a [`BlockCompression`] variant has in `fileformat.proto`
+fn compression_name(compression: &BlockCompression) -> &'static str {
+    match compression {
+        BlockCompression::Raw(_) => "raw",
+        BlockCompression::ZlibData(_) => "zlib_data",
+        BlockCompression::LzmaData(_) => "lzma_data",
+        BlockCompression::ObsoleteBzip2Data(_) => "OBSOLETE_bzip2_data",
+        BlockCompression::Lz4Data(_) => "lz4_data",
+        BlockCompression::ZstdData(_) => "zstd_data",
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ParseError {
     /// Failed to decompress blobs
@@ -51,4 +171,24 @@ pub enum ParseError {
     /// Failed to decode actual data
     #[error("Failed to decode data: {}", .0)]
     Decode(#[from] prost::DecodeError),
+
+    /// The blob uses a compression which isn't supported (or whose feature isn't enabled)
+    #[error("Unsupported compression: {}", .0)]
+    UnsupportedCompression(&'static str),
+
+    /// The blob's `raw_size` is negative or exceeds [`MAX_BLOB_SIZE`]
+    #[error("Invalid raw size: {}", .0)]
+    InvalidRawSize(i32),
+
+    /// The blob decompressed to more bytes than announced by its `raw_size` or allowed by [`MAX_BLOB_SIZE`]
+    #[error("Decompressed data exceeds {} bytes", .0)]
+    TooLarge(usize),
+
+    /// The string table contains invalid utf-8 at the given index
+    #[error("Invalid utf-8 in string table at index {}", .0)]
+    InvalidUtf8(usize),
+
+    /// The block is structurally inconsistent (only checked in strict mode)
+    #[error("Malformed block: {} inconsistencies", .0.len())]
+    Malformed(Vec<ValidationError>),
 }
@@ -0,0 +1,177 @@
+//! Parallel processing of an iterator's items with a bounded number of items in flight
+//!
+//! The iterators in this module process at most `window` items at the same time.
+//! This bounds the memory used by finished but not yet consumed results.
+//!
+//! A panic in the processing function is re-raised on the thread consuming the iterator.
+
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+
+use rayon::ThreadPool;
+
+/// Runs the processing function on a thread pool and sends the results back
+struct Spawner<F> {
+    pool: Arc<ThreadPool>,
+    process: Arc<F>,
+}
+
+impl<F> Spawner<F> {
+    fn new(pool: Arc<ThreadPool>, process: F) -> Self {
+        Self {
+            pool,
+            process: Arc::new(process),
+        }
+    }
+
+    /// Process `item` on the pool and send the result to `sender`
+    ///
+    /// Something is sent in any case: a panic in the processing function is caught and its payload is sent.
+    /// Otherwise the panic would abort the process, because the pool has no panic handler.
+    fn spawn<Item, T>(&self, item: Item, sender: SyncSender<thread::Result<Option<T>>>)
+    where
+        Item: Send + 'static,
+        T: Send + 'static,
+        F: Fn(Item) -> Option<T> + Send + Sync + 'static,
+    {
+        let process = self.process.clone();
+        self.pool.spawn(move || {
+            let result = catch_unwind(AssertUnwindSafe(|| process(item)));
+            // The receiver might have been dropped together with the iterator
+            let _ = sender.send(result);
+        });
+    }
+}
+
+/// Iterator which processes its inner iterator's items on a thread pool
+/// while yielding the results in the original order
+pub(crate) struct OrderedIter<I: Iterator, T, F> {
+    iter: I,
+    spawner: Spawner<F>,
+    window: usize,
+    pending: VecDeque<Receiver<thread::Result<Option<T>>>>,
+}
+
+impl<I, T, F> OrderedIter<I, T, F>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    T: Send + 'static,
+    F: Fn(I::Item) -> Option<T> + Send + Sync + 'static,
+{
+    pub(crate) fn new(iter: I, pool: Arc<ThreadPool>, window: usize, process: F) -> Self {
+        let window = window.max(1);
+        Self {
+            iter,
+            spawner: Spawner::new(pool, process),
+            window,
+            pending: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Start processing items until the window is full or the inner iterator is exhausted
+    fn fill(&mut self) {
+        while self.pending.len() < self.window {
+            let Some(item) = self.iter.next() else {
+                break;
+            };
+            let (sender, receiver) = sync_channel(1);
+            self.spawner.spawn(item, sender);
+            self.pending.push_back(receiver);
+        }
+    }
+}
+
+impl<I, T, F> Iterator for OrderedIter<I, T, F>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    T: Send + 'static,
+    F: Fn(I::Item) -> Option<T> + Send + Sync + 'static,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.fill();
+            match self.pending.pop_front()?.recv() {
+                Ok(Ok(Some(result))) => return Some(result),
+                Ok(Ok(None)) | Err(_) => {}
+                Ok(Err(payload)) => resume_unwind(payload),
+            }
+        }
+    }
+}
+
+/// Iterator which processes its inner iterator's items on a thread pool
+/// and yields the results as soon as they are finished
+pub(crate) struct UnorderedIter<I: Iterator, T, F> {
+    iter: I,
+    spawner: Spawner<F>,
+    window: usize,
+    in_flight: usize,
+    sender: SyncSender<thread::Result<Option<T>>>,
+    receiver: Receiver<thread::Result<Option<T>>>,
+}
+
+impl<I, T, F> UnorderedIter<I, T, F>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    T: Send + 'static,
+    F: Fn(I::Item) -> Option<T> + Send + Sync + 'static,
+{
+    pub(crate) fn new(iter: I, pool: Arc<ThreadPool>, window: usize, process: F) -> Self {
+        let window = window.max(1);
+        let (sender, receiver) = sync_channel(window);
+        Self {
+            iter,
+            spawner: Spawner::new(pool, process),
+            window,
+            in_flight: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Start processing items until the window is full or the inner iterator is exhausted
+    fn fill(&mut self) {
+        while self.in_flight < self.window {
+            let Some(item) = self.iter.next() else {
+                break;
+            };
+            self.spawner.spawn(item, self.sender.clone());
+            self.in_flight += 1;
+        }
+    }
+}
+
+impl<I, T, F> Iterator for UnorderedIter<I, T, F>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    T: Send + 'static,
+    F: Fn(I::Item) -> Option<T> + Send + Sync + 'static,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.fill();
+            if self.in_flight == 0 {
+                return None;
+            }
+            // Every task sends exactly once (see `Spawner::spawn`), which keeps `in_flight` accurate
+            let result = self.receiver.recv().ok()?;
+            self.in_flight -= 1;
+            match result {
+                Ok(Some(result)) => return Some(result),
+                Ok(None) => {}
+                Err(payload) => resume_unwind(payload),
+            }
+        }
+    }
+}
@@ -0,0 +1,512 @@
+//! Configurable reading of `.osm.pbf` files
+//!
+//! The `read...` functions in the crate's root use fixed defaults.
+//! [`ReaderOptions`] allows to configure their behavior.
+
+use std::fs::File;
+use std::io::Read;
+use std::iter;
+use std::path::Path;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use log::{debug, error, trace, warn};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::blobs::{
+    iter_blobs, iter_blobs_from_bytes, Blob, BlobIter, BlobPosition, BytesBlobIter, ReadError,
+};
+use crate::blocks::{Block, DataBlock, ElementKind, HeaderBlock, Utf8Policy};
+use crate::parse::{parse_blob_with_options, ParseOptions};
+use crate::pipeline::{OrderedIter, UnorderedIter};
+use crate::Error;
+
+crate::doc_imports! {
+    use crate::read;
+    use crate::read_par;
+    use crate::try_read;
+}
+
+/// How the `read...` functions handle errors while iterating over a file's blocks
+///
+/// Errors while reading the file always terminate the iteration, because the next blob can't be found after them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ErrorPolicy {
+    /// Log errors and skip their block
+    #[default]
+    Skip,
+
+    /// Log and yield the first error, then stop the iteration
+    Stop,
+
+    /// Yield errors and continue with the next block
+    ///
+    /// Each error is wrapped in an [`Error::Blob`] which states the position of the blob which caused it.
+    Return,
+}
+
+/// Builder configuring how a `.osm.pbf` file is read
+///
+/// The default options are used by [`read`], [`read_par`] and the crate's other `read...` functions:
+/// - errors are logged and their blocks skipped (see [`ErrorPolicy::Skip`])
+/// - all element types are decoded
+/// - invalid utf-8 is replaced (see [`Utf8Policy::Lossy`])
+/// - parallel reading uses one thread per cpu and allows two blobs per thread in flight
+/// - files requiring unknown features are rejected
+///
+/// The parallel readers decompress and parse blobs on a dedicated thread pool.
+/// Finished blocks wait until they are consumed and no new blob is read from the file while
+/// the maximum number of blobs are in flight (i.e. being parsed or waiting to be consumed).
+/// So the peak memory usage is roughly this maximum times the size of a decompressed block
+/// plus the blocks currently being processed by the consumer.
+#[derive(Copy, Clone, Debug)]
+pub struct ReaderOptions {
+    /// How to handle errors while iterating
+    errors: ErrorPolicy,
+
+    /// Which element types to keep indexed by [`ElementKind`]
+    elements: [bool; 3],
+
+    /// Options passed to [`parse_blob_with_options`]
+    parse: ParseOptions,
+
+    /// The number of threads used to parse blobs (`None` uses one per cpu)
+    threads: Option<usize>,
+
+    /// The maximum number of blobs in flight (`None` uses two per thread)
+    max_in_flight: Option<usize>,
+
+    /// Accept files whose header requires unknown features
+    accept_unknown_features: bool,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            errors: ErrorPolicy::default(),
+            elements: [true; 3],
+            parse: ParseOptions::default(),
+            threads: None,
+            max_in_flight: None,
+            accept_unknown_features: false,
+        }
+    }
+}
+
+impl ReaderOptions {
+    /// Construct the default options used by [`read`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how errors while iterating are handled
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.errors = policy;
+        self
+    }
+
+    /// Set which element types to decode
+    ///
+    /// Blocks are still decompressed and decoded as a whole,
+    /// but elements of other types are dropped before the block is yielded
+    /// and blocks which don't contain any remaining elements are skipped.
+    pub fn with_elements(mut self, kinds: impl IntoIterator<Item = ElementKind>) -> Self {
+        self.elements = [false; 3];
+        for kind in kinds {
+            self.elements[kind_index(kind)] = true;
+        }
+        self
+    }
+
+    /// Set how strings which are not valid utf-8 are handled
+    pub fn with_utf8_policy(mut self, policy: Utf8Policy) -> Self {
+        self.parse.utf8 = policy;
+        self
+    }
+
+    /// Reject blocks which fail [`DataBlock::validate`]
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.parse.strict = strict;
+        self
+    }
+
+    /// Set the number of threads used by the parallel readers to parse blobs
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    /// Set the maximum number of blobs which are being parsed or waiting to be consumed by the parallel readers
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight.max(1));
+        self
+    }
+
+    /// Accept files whose header requires features not supported by osmiumoxide
+    ///
+    /// Use [`HeaderBlock::required_features`] to inspect them yourself.
+    pub fn with_unknown_features(mut self, accept: bool) -> Self {
+        self.accept_unknown_features = accept;
+        self
+    }
+
+    /// Read a `.osm.pbf` file and return its header and an iterator over its blocks
+    ///
+    /// The iterator only yields errors when using [`ErrorPolicy::Stop`] or [`ErrorPolicy::Return`].
+    pub fn read(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(HeaderBlock, impl Iterator<Item = Result<DataBlock, Error>>), Error> {
+        self.read_blobs(iter_blobs(open(path.as_ref())?))
+    }
+
+    /// Read a `.osm.pbf` file from a reader and return its header and an iterator over its blocks
+    ///
+    /// Version of [`ReaderOptions::read`] for streams like stdin or a network connection
+    pub fn read_from<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<(HeaderBlock, impl Iterator<Item = Result<DataBlock, Error>>), Error> {
+        self.read_blobs(iter_blobs(reader))
+    }
+
+    /// Read a `.osm.pbf` file from memory and return its header and an iterator over its blocks
+    ///
+    /// Version of [`ReaderOptions::read`] which doesn't copy the blobs (see [`iter_blobs_from_bytes`])
+    pub fn read_bytes(
+        &self,
+        bytes: impl Into<Bytes>,
+    ) -> Result<(HeaderBlock, impl Iterator<Item = Result<DataBlock, Error>>), Error> {
+        self.read_blobs(iter_blobs_from_bytes(bytes))
+    }
+
+    /// Read a `.osm.pbf` file and return its header and an iterator over its blocks
+    ///
+    /// [`rayon`] version of [`ReaderOptions::read`] which yields the blocks in no particular order
+    pub fn read_par(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<
+        (
+            HeaderBlock,
+            impl ParallelIterator<Item = Result<DataBlock, Error>>,
+        ),
+        Error,
+    > {
+        self.read_par_blobs(iter_blobs(open(path.as_ref())?))
+    }
+
+    /// Read a `.osm.pbf` file from a reader and return its header and an iterator over its blocks
+    ///
+    /// [`rayon`] version of [`ReaderOptions::read_from`]
+    pub fn read_par_from<R: Read + Send>(
+        &self,
+        reader: R,
+    ) -> Result<
+        (
+            HeaderBlock,
+            impl ParallelIterator<Item = Result<DataBlock, Error>>,
+        ),
+        Error,
+    > {
+        self.read_par_blobs(iter_blobs(reader))
+    }
+
+    /// Read a `.osm.pbf` file from memory and return its header and an iterator over its blocks
+    ///
+    /// [`rayon`] version of [`ReaderOptions::read_bytes`]
+    pub fn read_par_bytes(
+        &self,
+        bytes: impl Into<Bytes>,
+    ) -> Result<
+        (
+            HeaderBlock,
+            impl ParallelIterator<Item = Result<DataBlock, Error>>,
+        ),
+        Error,
+    > {
+        self.read_par_blobs(iter_blobs_from_bytes(bytes))
+    }
+
+    /// Read a `.osm.pbf` file and return its header and an iterator over its blocks
+    ///
+    /// Parallel version of [`ReaderOptions::read`] which preserves the blocks' order:
+    /// blobs are decompressed and parsed on a dedicated thread pool while the iterator yields them in file order.
+    pub fn read_par_ordered(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(HeaderBlock, impl Iterator<Item = Result<DataBlock, Error>>), Error> {
+        let (header, blobs) = self.read_header(iter_blobs(open(path.as_ref())?))?;
+        let (pool, window) = self.build_pool()?;
+        let options = *self;
+        Ok((
+            header,
+            options.stop_after_error(OrderedIter::new(
+                iter_positioned(blobs),
+                pool,
+                window,
+                move |blob| options.process_blob(blob),
+            )),
+        ))
+    }
+
+    /// Shared implementation of the sequential `read...` methods
+    fn read_blobs(
+        &self,
+        blobs: impl BlobSource,
+    ) -> Result<(HeaderBlock, impl Iterator<Item = Result<DataBlock, Error>>), Error> {
+        let (header, blobs) = self.read_header(blobs)?;
+        let options = *self;
+        Ok((
+            header,
+            options.stop_after_error(
+                iter_positioned(blobs).filter_map(move |blob| options.process_blob(blob)),
+            ),
+        ))
+    }
+
+    /// Shared implementation of the parallel `read_par...` methods
+    fn read_par_blobs(
+        &self,
+        blobs: impl BlobSource + Send,
+    ) -> Result<
+        (
+            HeaderBlock,
+            impl ParallelIterator<Item = Result<DataBlock, Error>>,
+        ),
+        Error,
+    > {
+        let (header, blobs) = self.read_header(blobs)?;
+        let (pool, window) = self.build_pool()?;
+        let options = *self;
+        Ok((
+            header,
+            options
+                .stop_after_error(UnorderedIter::new(
+                    iter_positioned(blobs),
+                    pool,
+                    window,
+                    move |blob| options.process_blob(blob),
+                ))
+                .par_bridge(),
+        ))
+    }
+
+    /// Read and check the file's header
+    fn read_header<B: BlobSource>(&self, mut blobs: B) -> Result<(HeaderBlock, B), Error> {
+        let blob = blobs.next().ok_or(Error::MissingHeader)??;
+        let Block::Header(header) = parse_blob_with_options(blob, self.parse)? else {
+            return Err(Error::MissingHeader);
+        };
+
+        trace!("File header: {header:#?}");
+        if let Some(feature) = header.unknown_required_features() {
+            if !self.accept_unknown_features {
+                return Err(Error::UnknownFeature(feature.to_string()));
+            }
+            warn!("File requires unknown feature \"{feature}\"");
+        }
+
+        Ok((header, blobs))
+    }
+
+    /// Build the thread pool and compute the number of blobs in flight
+    fn build_pool(&self) -> Result<(Arc<ThreadPool>, usize), Error> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(self.threads.unwrap_or(0))
+            .thread_name(|index| format!("osmiumoxide-parse-{index}"))
+            .build()
+            .map_err(Error::ThreadPool)?;
+        let window = self.max_in_flight.unwrap_or(2 * pool.current_num_threads());
+        Ok((Arc::new(pool), window))
+    }
+
+    /// Parse a single blob applying the element filter and the error policy
+    fn process_blob(
+        &self,
+        (position, result): (BlobPosition, Result<Blob, ReadError>),
+    ) -> Option<Result<DataBlock, Error>> {
+        let block = result
+            .map_err(Error::from)
+            .and_then(|blob| parse_blob_with_options(blob, self.parse).map_err(Error::from));
+        match block {
+            Ok(Block::Data(mut block)) => {
+                if self.elements != [true; 3] {
+                    block.retain_elements(|kind| self.elements[kind_index(kind)]);
+                    if block.is_empty() {
+                        return None;
+                    }
+                }
+                Some(Ok(block))
+            }
+            Ok(Block::Header(_)) => {
+                warn!("Skipping header block");
+                None
+            }
+            Ok(Block::Unknown(string, _)) => {
+                warn!("Skipping unknown block of type \"{string}\"");
+                None
+            }
+            Err(error) => {
+                let error = Error::Blob {
+                    position,
+                    error: Box::new(error),
+                };
+                if self.errors != ErrorPolicy::Return {
+                    error!("Failed to process {position}");
+                    debug!("{error}");
+                }
+                match self.errors {
+                    ErrorPolicy::Skip => None,
+                    ErrorPolicy::Stop | ErrorPolicy::Return => Some(Err(error)),
+                }
+            }
+        }
+    }
+
+    /// End the iteration after yielding the first error if using [`ErrorPolicy::Stop`]
+    ///
+    /// The parallel readers apply this before handing the blocks to [`rayon`],
+    /// so no further blobs are read after the error.
+    fn stop_after_error(
+        &self,
+        blocks: impl Iterator<Item = Result<DataBlock, Error>>,
+    ) -> impl Iterator<Item = Result<DataBlock, Error>> {
+        let stop = self.errors == ErrorPolicy::Stop;
+        let mut stopped = false;
+        blocks.map_while(move |result| {
+            if stopped {
+                return None;
+            }
+            stopped = stop && result.is_err();
+            Some(result)
+        })
+    }
+}
+
+/// Index of an [`ElementKind`] in [`ReaderOptions`]'s element filter
+fn kind_index(kind: ElementKind) -> usize {
+    match kind {
+        ElementKind::Node => 0,
+        ElementKind::Way => 1,
+        ElementKind::Relation => 2,
+    }
+}
+
+/// Open a file mapping the error to [`Error::FileError`]
+fn open(path: &Path) -> Result<File, Error> {
+    File::open(path).map_err(Error::FileError)
+}
+
+/// Iterator over blobs which knows the position of the next blob
+trait BlobSource: Iterator<Item = Result<Blob, ReadError>> {
+    /// The position of the blob which will be returned by the next call to [`Iterator::next`]
+    fn position(&self) -> BlobPosition;
+}
+impl<R: Read> BlobSource for BlobIter<R> {
+    fn position(&self) -> BlobPosition {
+        self.position()
+    }
+}
+impl BlobSource for BytesBlobIter {
+    fn position(&self) -> BlobPosition {
+        self.position()
+    }
+}
+
+/// Attach positions to blobs
+///
+/// The returned iterator stops after the first error, because the reader can't find the next blob after it.
+fn iter_positioned(
+    mut blobs: impl BlobSource,
+) -> impl Iterator<Item = (BlobPosition, Result<Blob, ReadError>)> {
+    let mut failed = false;
+    iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let position = blobs.position();
+        let result = blobs.next()?;
+        failed = result.is_err();
+        Some((position, result))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rayon::prelude::*;
+
+    use crate::blobs::{Blob, BlobType};
+    use crate::blocks::{Block, DataBlock, HeaderBlock};
+    use crate::reader::{ErrorPolicy, ReaderOptions};
+    use crate::write::{BlobWriter, BlockBuilder, Compression};
+    use crate::{proto, Error};
+
+    /// A file whose second data blob is corrupted
+    fn corrupted_file() -> Vec<u8> {
+        let header = HeaderBlock::new(proto::HeaderBlock {
+            required_features: vec!["OsmSchema-V0.6".to_string(), "DenseNodes".to_string()],
+            ..Default::default()
+        });
+        let mut writer = BlobWriter::new(Vec::new());
+        writer
+            .write_block(&Block::Header(header), Compression::default())
+            .unwrap();
+        for id in [1, 2, 3] {
+            if id == 2 {
+                let corrupted = Blob {
+                    r#type: BlobType::OSMData,
+                    data: Bytes::from_static(b"garbage"),
+                };
+                writer.write_blob(&corrupted).unwrap();
+            } else {
+                let mut builder = BlockBuilder::new();
+                builder.add_node(id, 0, 0, [("name", "a")]);
+                let block = Block::Data(builder.flush().unwrap());
+                writer.write_block(&block, Compression::default()).unwrap();
+            }
+        }
+        writer.into_inner()
+    }
+
+    /// Reduce a block to its first node's id and an error to its blob's index
+    fn summarize(result: Result<DataBlock, Error>) -> Result<i64, usize> {
+        match result {
+            Ok(block) => Ok(block.iter_nodes().next().unwrap().id()),
+            Err(Error::Blob { position, .. }) => Err(position.index),
+            Err(error) => panic!("Expected a blob error but got {error}"),
+        }
+    }
+
+    #[test]
+    fn error_policies() {
+        let file = Bytes::from(corrupted_file());
+        for (policy, expected) in [
+            (ErrorPolicy::Skip, vec![Ok(1), Ok(3)]),
+            (ErrorPolicy::Return, vec![Ok(1), Err(2), Ok(3)]),
+            (ErrorPolicy::Stop, vec![Ok(1), Err(2)]),
+        ] {
+            let options = ReaderOptions::new().with_error_policy(policy);
+
+            let (_, blocks) = options.read_bytes(file.clone()).unwrap();
+            let actual: Vec<_> = blocks.map(summarize).collect();
+            assert_eq!(actual, expected, "read_bytes with {policy:?}");
+
+            let (_, blocks) = options.read_par_bytes(file.clone()).unwrap();
+            let mut actual: Vec<_> = blocks.map(summarize).collect();
+            actual.sort_by_key(|result| match result {
+                Ok(id) => *id as usize,
+                Err(index) => *index,
+            });
+            if policy == ErrorPolicy::Stop {
+                // Blocks finished concurrently with the corrupted one may or may not be yielded
+                let errors: Vec<_> = actual.iter().filter(|result| result.is_err()).collect();
+                assert_eq!(errors, [&Err(2)], "read_par_bytes with {policy:?}");
+            } else {
+                assert_eq!(actual, expected, "read_par_bytes with {policy:?}");
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! Helpful iterator structs
 
+use std::iter::Peekable;
 use std::ops::Add;
 
 pub trait IteratorExt: Iterator + Sized {
@@ -15,6 +16,13 @@ pub trait IteratorExt: Iterator + Sized {
         ChunkPairs(self)
     }
 
+    fn group_by_key<K: PartialEq, F: FnMut(&Self::Item) -> K>(self, key: F) -> GroupByKey<Self, F> {
+        GroupByKey {
+            iter: self.peekable(),
+            key,
+        }
+    }
+
     fn decode_delta(self) -> DecodeDelta<Self>
     where
         Self::Item: Copy + Default + Add<Output = Self::Item>,
@@ -74,3 +82,22 @@ impl<I: Iterator> Iterator for ChunkPairs<I> {
         Some((fst, snd))
     }
 }
+
+/// Iterator which collects consecutive items with equal keys into a [`Vec`]
+pub struct GroupByKey<I: Iterator, F> {
+    iter: Peekable<I>,
+    key: F,
+}
+impl<I: Iterator, K: PartialEq, F: FnMut(&I::Item) -> K> Iterator for GroupByKey<I, F> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.iter.next()?;
+        let key = (self.key)(&first);
+        let mut group = vec![first];
+        while let Some(item) = self.iter.next_if(|item| (self.key)(item) == key) {
+            group.push(item);
+        }
+        Some(group)
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+use std::mem;
+
+use crate::blocks::{DataBlock, MemberType};
+use crate::proto;
+
+crate::doc_imports! {
+    use crate::blocks::Node;
+}
+
+/// Builds [`DataBlock`]s from individual nodes, ways and relations
+///
+/// The builder takes care of the `.osm.pbf` specific encoding:
+/// - strings are deduplicated into the block's [`proto::StringTable`] which is sorted by frequency
+/// - nodes are stored as [`proto::DenseNodes`]
+/// - ids, coordinates, way refs and relation members are delta-encoded
+/// - coordinates are stored using the configured granularity and offsets
+///
+/// Each `add_...` method returns a finished block once the configured number of entities is reached.
+/// Don't forget to call [`BlockBuilder::flush`] after adding the last entity to retrieve the remaining ones.
+///
+/// Nodes, ways and relations are stored in separate groups in that order,
+/// so sorted input produces sorted blocks.
+#[derive(Debug)]
+pub struct BlockBuilder {
+    /// The number of entities after which a block is flushed
+    max_entities: usize,
+
+    /// Granularity of coordinates in nanodegrees
+    granularity: i32,
+
+    /// Offset of latitudes in nanodegrees
+    lat_offset: i64,
+
+    /// Offset of longitudes in nanodegrees
+    lon_offset: i64,
+
+    strings: StringTableBuilder,
+    nodes: Vec<PendingNode>,
+    ways: Vec<PendingWay>,
+    relations: Vec<PendingRelation>,
+}
+
+impl Default for BlockBuilder {
+    fn default() -> Self {
+        Self {
+            max_entities: 8000,
+            granularity: 100,
+            lat_offset: 0,
+            lon_offset: 0,
+            strings: StringTableBuilder::default(),
+            nodes: Vec::new(),
+            ways: Vec::new(),
+            relations: Vec::new(),
+        }
+    }
+}
+
+impl BlockBuilder {
+    /// Construct a builder which flushes every 8000 entities (like osmium) and uses the default granularity of 100
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of entities after which a block is flushed
+    pub fn with_max_entities(mut self, max_entities: usize) -> Self {
+        self.max_entities = max_entities.max(1);
+        self
+    }
+
+    /// Set the granularity of coordinates in nanodegrees
+    pub fn with_granularity(mut self, granularity: i32) -> Self {
+        self.granularity = granularity.max(1);
+        self
+    }
+
+    /// Set the offsets of coordinates in nanodegrees
+    pub fn with_offsets(mut self, lat_offset: i64, lon_offset: i64) -> Self {
+        self.lat_offset = lat_offset;
+        self.lon_offset = lon_offset;
+        self
+    }
+
+    /// The number of entities added since the last flush
+    pub fn len(&self) -> usize {
+        self.nodes.len() + self.ways.len() + self.relations.len()
+    }
+
+    /// Returns true if no entities were added since the last flush
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add a node whose coordinates are given in nanodegrees
+    pub fn add_node<K: AsRef<str>, V: AsRef<str>>(
+        &mut self,
+        id: i64,
+        lat: i64,
+        lon: i64,
+        tags: impl IntoIterator<Item = (K, V)>,
+    ) -> Option<DataBlock> {
+        let tags = self.strings.intern_tags(tags);
+        self.nodes.push(PendingNode { id, lat, lon, tags });
+        self.flush_if_full()
+    }
+
+    /// Add a way referencing its nodes by their ids
+    pub fn add_way<K: AsRef<str>, V: AsRef<str>>(
+        &mut self,
+        id: i64,
+        refs: impl IntoIterator<Item = i64>,
+        tags: impl IntoIterator<Item = (K, V)>,
+    ) -> Option<DataBlock> {
+        let tags = self.strings.intern_tags(tags);
+        let refs = refs.into_iter().collect();
+        self.ways.push(PendingWay { id, refs, tags });
+        self.flush_if_full()
+    }
+
+    /// Add a relation whose members are given as `(id, type, role)`
+    pub fn add_relation<K: AsRef<str>, V: AsRef<str>, R: AsRef<str>>(
+        &mut self,
+        id: i64,
+        members: impl IntoIterator<Item = (i64, MemberType, R)>,
+        tags: impl IntoIterator<Item = (K, V)>,
+    ) -> Option<DataBlock> {
+        let tags = self.strings.intern_tags(tags);
+        let members = members
+            .into_iter()
+            .map(|(id, r#type, role)| (id, r#type, self.strings.intern(role.as_ref())))
+            .collect();
+        self.relations.push(PendingRelation { id, members, tags });
+        self.flush_if_full()
+    }
+
+    /// Build a block from all entities added since the last flush
+    ///
+    /// Returns `None` if there are none.
+    pub fn flush(&mut self) -> Option<DataBlock> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (stringtable, remap) = mem::take(&mut self.strings).finish();
+        let remap_tags = |tags: &[(u32, u32)]| -> (Vec<u32>, Vec<u32>) {
+            tags.iter()
+                .map(|(key, value)| (remap[*key as usize], remap[*value as usize]))
+                .unzip()
+        };
+
+        let mut primitivegroup = Vec::new();
+
+        let nodes = mem::take(&mut self.nodes);
+        if !nodes.is_empty() {
+            let mut dense = proto::DenseNodes::default();
+            let mut id = DeltaEncoder::default();
+            let mut lat = DeltaEncoder::default();
+            let mut lon = DeltaEncoder::default();
+            let has_tags = nodes.iter().any(|node| !node.tags.is_empty());
+            for node in nodes.iter() {
+                dense.id.push(id.encode(node.id));
+                dense
+                    .lat
+                    .push(lat.encode(self.encode_coord(node.lat, self.lat_offset)));
+                dense
+                    .lon
+                    .push(lon.encode(self.encode_coord(node.lon, self.lon_offset)));
+                if has_tags {
+                    for (key, value) in node.tags.iter() {
+                        dense.keys_vals.push(remap[*key as usize] as i32);
+                        dense.keys_vals.push(remap[*value as usize] as i32);
+                    }
+                    dense.keys_vals.push(0);
+                }
+            }
+            primitivegroup.push(proto::PrimitiveGroup {
+                dense: Some(dense),
+                ..Default::default()
+            });
+        }
+
+        let ways = mem::take(&mut self.ways);
+        if !ways.is_empty() {
+            let ways = ways
+                .iter()
+                .map(|way| {
+                    let (keys, vals) = remap_tags(&way.tags);
+                    let mut refs = DeltaEncoder::default();
+                    proto::Way {
+                        id: way.id,
+                        keys,
+                        vals,
+                        info: None,
+                        refs: way.refs.iter().map(|id| refs.encode(*id)).collect(),
+                        lat: Vec::new(),
+                        lon: Vec::new(),
+                    }
+                })
+                .collect();
+            primitivegroup.push(proto::PrimitiveGroup {
+                ways,
+                ..Default::default()
+            });
+        }
+
+        let relations = mem::take(&mut self.relations);
+        if !relations.is_empty() {
+            let relations = relations
+                .iter()
+                .map(|relation| {
+                    let (keys, vals) = remap_tags(&relation.tags);
+                    let mut memids = DeltaEncoder::default();
+                    proto::Relation {
+                        id: relation.id,
+                        keys,
+                        vals,
+                        info: None,
+                        roles_sid: relation
+                            .members
+                            .iter()
+                            .map(|(_, _, role)| remap[*role as usize] as i32)
+                            .collect(),
+                        memids: relation
+                            .members
+                            .iter()
+                            .map(|(id, _, _)| memids.encode(*id))
+                            .collect(),
+                        types: relation
+                            .members
+                            .iter()
+                            .map(|(_, r#type, _)| *r#type as i32)
+                            .collect(),
+                    }
+                })
+                .collect();
+            primitivegroup.push(proto::PrimitiveGroup {
+                relations,
+                ..Default::default()
+            });
+        }
+
+        Some(DataBlock::new(proto::PrimitiveBlock {
+            stringtable,
+            primitivegroup,
+            granularity: Some(self.granularity),
+            lat_offset: Some(self.lat_offset),
+            lon_offset: Some(self.lon_offset),
+            date_granularity: None,
+        }))
+    }
+
+    fn flush_if_full(&mut self) -> Option<DataBlock> {
+        if self.len() >= self.max_entities {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Convert a coordinate in nanodegrees into the raw value stored in a block
+    ///
+    /// This is the inverse of the conversion performed by [`Node::lat`] and [`Node::lon`].
+    fn encode_coord(&self, nano: i64, offset: i64) -> i64 {
+        let granularity = self.granularity as i64;
+        (nano - offset + granularity / 2).div_euclid(granularity)
+    }
+}
+
+/// A node which has been added to a [`BlockBuilder`] but not flushed yet
+#[derive(Debug)]
+struct PendingNode {
+    id: i64,
+    lat: i64,
+    lon: i64,
+    tags: Vec<(u32, u32)>,
+}
+
+/// A way which has been added to a [`BlockBuilder`] but not flushed yet
+#[derive(Debug)]
+struct PendingWay {
+    id: i64,
+    refs: Vec<i64>,
+    tags: Vec<(u32, u32)>,
+}
+
+/// A relation which has been added to a [`BlockBuilder`] but not flushed yet
+#[derive(Debug)]
+struct PendingRelation {
+    id: i64,
+    members: Vec<(i64, MemberType, u32)>,
+    tags: Vec<(u32, u32)>,
+}
+
+/// Deduplicates strings and counts their occurrences
+///
+/// The indices handed out by [`StringTableBuilder::intern`] are only temporary
+/// and have to be remapped using the table returned by [`StringTableBuilder::finish`].
+#[derive(Debug, Default)]
+struct StringTableBuilder {
+    /// Map from a string to its temporary index
+    indices: HashMap<String, u32>,
+
+    /// The strings and their number of occurrences ordered by their temporary index
+    strings: Vec<(String, usize)>,
+}
+
+impl StringTableBuilder {
+    fn intern(&mut self, string: &str) -> u32 {
+        if let Some(index) = self.indices.get(string) {
+            self.strings[*index as usize].1 += 1;
+            return *index;
+        }
+        let index = self.strings.len() as u32;
+        self.indices.insert(string.to_string(), index);
+        self.strings.push((string.to_string(), 1));
+        index
+    }
+
+    fn intern_tags<K: AsRef<str>, V: AsRef<str>>(
+        &mut self,
+        tags: impl IntoIterator<Item = (K, V)>,
+    ) -> Vec<(u32, u32)> {
+        tags.into_iter()
+            .map(|(key, value)| (self.intern(key.as_ref()), self.intern(value.as_ref())))
+            .collect()
+    }
+
+    /// Sort the strings by frequency and produce the final table
+    ///
+    /// Returns the table and a map from temporary indices to final ones.
+    fn finish(mut self) -> (proto::StringTable, Vec<u32>) {
+        let mut order: Vec<usize> = (0..self.strings.len()).collect();
+        order.sort_by(|a, b| {
+            let (a_string, a_count) = &self.strings[*a];
+            let (b_string, b_count) = &self.strings[*b];
+            b_count.cmp(a_count).then_with(|| a_string.cmp(b_string))
+        });
+
+        // Index 0 is reserved as delimiter and always empty
+        let mut s = Vec::with_capacity(order.len() + 1);
+        s.push(Vec::new().into());
+        let mut remap = vec![0; order.len()];
+        for (final_index, temp_index) in order.into_iter().enumerate() {
+            remap[temp_index] = final_index as u32 + 1;
+            s.push(
+                mem::take(&mut self.strings[temp_index].0)
+                    .into_bytes()
+                    .into(),
+            );
+        }
+
+        (proto::StringTable { s }, remap)
+    }
+}
+
+/// Helper for delta-encoding a sequence of values
+#[derive(Debug, Default)]
+struct DeltaEncoder {
+    previous: i64,
+}
+
+impl DeltaEncoder {
+    fn encode(&mut self, value: i64) -> i64 {
+        let delta = value - self.previous;
+        self.previous = value;
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::{
+        Block, HeaderBlock, MemberType, OwnedElement, OwnedMember, OwnedNode, OwnedRelation,
+        OwnedWay,
+    };
+    use crate::coords::LatLon;
+    use crate::proto;
+    use crate::write::{BlobWriter, BlockBuilder, Compression};
+
+    fn tags(tags: &[(&str, &str)]) -> Vec<(String, String)> {
+        tags.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut builder = BlockBuilder::new()
+            .with_max_entities(3)
+            .with_granularity(1000)
+            .with_offsets(500, -500);
+        let mut blocks = Vec::new();
+        blocks.extend(builder.add_node(1, 51_123_456_789, 7_000_000_499, [("name", "a")]));
+        blocks.extend(builder.add_node(
+            5,
+            -33_999_999_999,
+            -70_000_000_001,
+            [] as [(&str, &str); 0],
+        ));
+        blocks.extend(builder.add_node(3, 0, -500, [("name", "name"), ("amenity", "bench")]));
+        blocks.extend(builder.add_way(10, [5, 3, 1], [("highway", "path"), ("name", "a")]));
+        blocks.extend(builder.add_way(9, [1, 5], [] as [(&str, &str); 0]));
+        blocks.extend(builder.add_relation(
+            20,
+            [
+                (10, MemberType::Way, "outer"),
+                (1, MemberType::Node, ""),
+                (21, MemberType::Relation, "outer"),
+            ],
+            [("type", "multipolygon")],
+        ));
+        blocks.extend(builder.flush());
+        assert!(builder.is_empty());
+        assert_eq!(blocks.len(), 2);
+
+        let mut writer = BlobWriter::new(Vec::new());
+        let header = HeaderBlock::new(proto::HeaderBlock {
+            required_features: vec!["OsmSchema-V0.6".to_string(), "DenseNodes".to_string()],
+            ..Default::default()
+        });
+        writer
+            .write_block(&Block::Header(header), Compression::default())
+            .unwrap();
+        for block in blocks {
+            writer
+                .write_block(&Block::Data(block), Compression::default())
+                .unwrap();
+        }
+
+        let (_, blocks) = crate::read_bytes(writer.into_inner()).unwrap();
+        let elements: Vec<_> = blocks
+            .flat_map(|block| {
+                block
+                    .iter_elements()
+                    .map(|element| element.to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let node = |id, lat, lon, tags| {
+            OwnedElement::Node(OwnedNode {
+                id,
+                location: LatLon::new(lat, lon),
+                tags,
+                info: None,
+            })
+        };
+        let member = |id, r#type, role: &str| OwnedMember {
+            id,
+            r#type,
+            role: role.to_string(),
+        };
+        assert_eq!(
+            elements,
+            [
+                // Coordinates are rounded to the granularity relative to the offsets
+                node(1, 51_123_456_500, 7_000_000_500, tags(&[("name", "a")])),
+                node(5, -33_999_999_500, -70_000_000_500, tags(&[])),
+                node(
+                    3,
+                    500,
+                    -500,
+                    tags(&[("name", "name"), ("amenity", "bench")])
+                ),
+                OwnedElement::Way(OwnedWay {
+                    id: 10,
+                    nodes: vec![5, 3, 1],
+                    locations: Vec::new(),
+                    tags: tags(&[("highway", "path"), ("name", "a")]),
+                    info: None,
+                }),
+                OwnedElement::Way(OwnedWay {
+                    id: 9,
+                    nodes: vec![1, 5],
+                    locations: Vec::new(),
+                    tags: tags(&[]),
+                    info: None,
+                }),
+                OwnedElement::Relation(OwnedRelation {
+                    id: 20,
+                    members: vec![
+                        member(10, MemberType::Way, "outer"),
+                        member(1, MemberType::Node, ""),
+                        member(21, MemberType::Relation, "outer"),
+                    ],
+                    tags: tags(&[("type", "multipolygon")]),
+                    info: None,
+                }),
+            ]
+        );
+    }
+}
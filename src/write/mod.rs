@@ -0,0 +1,235 @@
+//! Writing `.osm.pbf` files
+//!
+//! This module is the mirror image of [`blobs::iter_blobs`] and [`parse::parse_blob`]:
+//! - [`encode_block`] encodes and compresses a [`Block`] into a [`Blob`]
+//! - [`BlockBuilder`] assembles a [`DataBlock`] from individual nodes, ways and relations
+//! - [`BlobWriter`] prefixes [`Blob`]s with their [`proto::BlobHeader`] and writes them to an [`io::Write`]
+//!
+//! A valid file has to start with a [`Block::Header`] followed by any number of [`Block::Data`].
+
+mod builder;
+
+use std::io;
+use std::io::Write;
+
+use bytes::Bytes;
+use flate2::write::ZlibEncoder;
+use prost::Message;
+use thiserror::Error;
+
+pub use self::builder::BlockBuilder;
+use crate::blobs::{Blob, BlobType, MAX_BLOB_HEADER_SIZE, MAX_BLOB_SIZE};
+use crate::blocks::Block;
+use crate::parse::BlockCompression;
+use crate::proto;
+
+crate::doc_imports! {
+    use crate::blobs;
+    use crate::blocks::DataBlock;
+    use crate::parse;
+}
+
+/// The compression to apply to a [`Blob`]'s data
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// Store the data uncompressed
+    Raw,
+
+    /// Compress the data using zlib with a level between 0 (fastest) and 9 (smallest)
+    Zlib(u32),
+}
+impl Default for Compression {
+    /// Zlib with level 6 which is zlib's own default
+    fn default() -> Self {
+        Self::Zlib(6)
+    }
+}
+
+/// Encode and compress a [`Block`] into a [`Blob`]
+///
+/// This is the inverse of [`parse::parse_blob`].
+pub fn encode_block(block: &Block, compression: Compression) -> Result<Blob, WriteError> {
+    let (r#type, raw) = match block {
+        Block::Header(header) => (BlobType::OSMHeader, header.as_proto().encode_to_vec()),
+        Block::Data(data) => (BlobType::OSMData, data.as_proto().encode_to_vec()),
+        Block::Unknown(string, raw) => (BlobType::Unknown(string.clone()), raw.to_vec()),
+    };
+    if raw.len() > MAX_BLOB_SIZE {
+        return Err(WriteError::BlobTooLarge(raw.len()));
+    }
+
+    let (raw_size, data) = match compression {
+        Compression::Raw => (None, BlockCompression::Raw(raw.into())),
+        Compression::Zlib(level) => {
+            let raw_size = raw.len() as i32;
+            let mut encoder = ZlibEncoder::new(
+                Vec::with_capacity(raw.len() / 2),
+                flate2::Compression::new(level),
+            );
+            encoder.write_all(&raw)?;
+            let encoded = encoder.finish()?;
+            (Some(raw_size), BlockCompression::ZlibData(encoded.into()))
+        }
+    };
+
+    let data = proto::Blob {
+        raw_size,
+        data: Some(data),
+    }
+    .encode_to_vec();
+    if data.len() > MAX_BLOB_SIZE {
+        return Err(WriteError::BlobTooLarge(data.len()));
+    }
+
+    Ok(Blob {
+        r#type,
+        data: Bytes::from(data),
+    })
+}
+
+/// Writes [`Blob`]s to an `.osm.pbf` file
+///
+/// This is the inverse of [`blobs::BlobIter`].
+#[derive(Debug)]
+pub struct BlobWriter<W: Write>(W);
+impl<W: Write> BlobWriter<W> {
+    /// Wrap a writer
+    pub fn new(writer: W) -> Self {
+        Self(writer)
+    }
+
+    /// Write a single [`Blob`] prefixed by its length and [`proto::BlobHeader`]
+    pub fn write_blob(&mut self, blob: &Blob) -> Result<(), WriteError> {
+        let header = proto::BlobHeader {
+            r#type: blob.r#type.to_string(),
+            indexdata: None,
+            datasize: i32::try_from(blob.data.len())
+                .map_err(|_| WriteError::BlobTooLarge(blob.data.len()))?,
+        }
+        .encode_to_vec();
+        if header.len() > MAX_BLOB_HEADER_SIZE {
+            return Err(WriteError::HeaderTooLarge(header.len()));
+        }
+
+        self.0.write_all(&(header.len() as u32).to_be_bytes())?;
+        self.0.write_all(&header)?;
+        self.0.write_all(&blob.data)?;
+        Ok(())
+    }
+
+    /// Encode, compress and write a single [`Block`]
+    ///
+    /// Shorthand for [`encode_block`] followed by [`BlobWriter::write_blob`].
+    pub fn write_block(
+        &mut self,
+        block: &Block,
+        compression: Compression,
+    ) -> Result<(), WriteError> {
+        self.write_blob(&encode_block(block, compression)?)
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), WriteError> {
+        Ok(self.0.flush()?)
+    }
+
+    /// Unwrap the underlying writer
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+/// An error which occurred while writing an `.osm.pbf` file
+#[derive(Error, Debug)]
+pub enum WriteError {
+    /// Failed to write or compress data
+    #[error("Failed to write file: {}", .0)]
+    Io(#[from] io::Error),
+
+    /// The blob's size exceeds [`MAX_BLOB_SIZE`]
+    #[error("Blob is too large: {} bytes", .0)]
+    BlobTooLarge(usize),
+
+    /// The blob header's size exceeds [`MAX_BLOB_HEADER_SIZE`]
+    #[error("Blob header is too large: {} bytes", .0)]
+    HeaderTooLarge(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::blobs::{iter_blobs, BlobType};
+    use crate::blocks::{Block, HeaderBlock};
+    use crate::parse::parse_blob;
+    use crate::proto;
+    use crate::write::{encode_block, BlobWriter, BlockBuilder, Compression};
+
+    fn blocks() -> Vec<Block> {
+        let header = HeaderBlock::new(proto::HeaderBlock {
+            required_features: vec!["OsmSchema-V0.6".to_string(), "DenseNodes".to_string()],
+            writingprogram: Some("osmiumoxide".to_string()),
+            ..Default::default()
+        });
+        let mut builder = BlockBuilder::new();
+        builder.add_node(1, 10, 20, [("name", "a")]);
+        builder.add_way(2, [1, 1], [("highway", "path")]);
+        let data = builder.flush().unwrap();
+        vec![
+            Block::Header(header),
+            Block::Data(data),
+            Block::Unknown("Custom".to_string(), Bytes::from_static(b"custom data")),
+        ]
+    }
+
+    #[test]
+    fn encode_parse_round_trip() {
+        for compression in [Compression::Raw, Compression::Zlib(0), Compression::Zlib(9)] {
+            for block in blocks() {
+                let blob = encode_block(&block, compression).unwrap();
+                match (block, parse_blob(blob).unwrap()) {
+                    (Block::Header(expected), Block::Header(actual)) => {
+                        assert_eq!(expected.as_proto(), actual.as_proto())
+                    }
+                    (Block::Data(expected), Block::Data(actual)) => {
+                        assert_eq!(expected.as_proto(), actual.as_proto())
+                    }
+                    (
+                        Block::Unknown(expected, expected_raw),
+                        Block::Unknown(actual, actual_raw),
+                    ) => {
+                        assert_eq!(expected, actual);
+                        assert_eq!(expected_raw, actual_raw);
+                    }
+                    (expected, actual) => panic!("Expected {expected:?} but got {actual:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn writer_frames_blobs() {
+        let blobs: Vec<_> = blocks()
+            .iter()
+            .map(|block| encode_block(block, Compression::default()).unwrap())
+            .collect();
+
+        let mut writer = BlobWriter::new(Vec::new());
+        for blob in blobs.iter() {
+            writer.write_blob(blob).unwrap();
+        }
+        writer.flush().unwrap();
+        let file = writer.into_inner();
+
+        let mut read = iter_blobs(file.as_slice());
+        for (index, expected) in blobs.iter().enumerate() {
+            assert_eq!(read.position().index, index);
+            let actual = read.next().unwrap().unwrap();
+            assert_eq!(actual.r#type.to_string(), expected.r#type.to_string());
+            assert_eq!(actual.data, expected.data);
+        }
+        assert_eq!(read.position().offset, file.len() as u64);
+        assert!(read.next().is_none());
+        assert!(matches!(blobs[0].r#type, BlobType::OSMHeader));
+    }
+}